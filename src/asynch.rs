@@ -0,0 +1,459 @@
+use crate::channel_set::ChannelSet;
+use crate::driver::TouchDriver;
+use crate::hal::TouchHal;
+use crate::interrupt::{Subscription, TouchInterrupt};
+use crate::queue::{queue, Consumer};
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use atomic_waker::AtomicWaker;
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Interrupts kept for `TouchEvents` until they are polled.
+const EVENT_QUEUE_LEN: usize = 16;
+
+/// Period at which the futures waiting for a condition that raises no interrupt check it. A
+/// measurement takes a few milliseconds with the default measurement time.
+const POLL_PERIOD: Duration = Duration::from_millis(1);
+
+/// State written by the ISR and the ticker, and read by the futures.
+#[derive(Default)]
+struct Shared {
+    waker: AtomicWaker,
+    /// Channels reported by the interrupts since the last `take_touched`.
+    touched: AtomicU16,
+    /// Whether the ticker has to wake the futures every `POLL_PERIOD`.
+    polling: AtomicBool,
+}
+
+impl Shared {
+    fn take_touched(&self, channels: ChannelSet) -> ChannelSet {
+        let bits = self.touched.fetch_and(!channels.bits(), Ordering::AcqRel);
+        ChannelSet::from_bits_truncate((bits & channels.bits()) as u32)
+    }
+}
+
+/// Async access to the touch interrupts. Every future is woken from the touch ISR, so they run
+/// on any executor. `measure` and `wait_for_release` wait for conditions that raise no
+/// interrupt, they are woken every `POLL_PERIOD` by a thread started on first use and kept
+/// until the `AsyncTouch` is dropped.
+///
+/// Interrupts are enabled when it is created, and disabled again when it is dropped if they
+/// were disabled before. Every method takes `&mut self`, so one future is pending at a time.
+pub struct AsyncTouch<'d, H: TouchHal + Sync> {
+    driver: &'d TouchDriver<H>,
+    shared: Arc<Shared>,
+    events: Consumer<TouchInterrupt, EVENT_QUEUE_LEN>,
+    ticker: Option<Ticker>,
+    intr_was_enabled: bool,
+    _subscription: Subscription<'d, H>,
+}
+
+impl<'d, H: TouchHal + Sync> AsyncTouch<'d, H> {
+    /// Subscribe to the touch interrupt and enable it.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG GPIO error
+    /// * ESP_ERR_NO_MEM No memory
+    pub fn new(driver: &'d TouchDriver<H>) -> Result<Self, TouchError> {
+        let shared = Arc::new(Shared::default());
+        let (mut producer, events) = queue();
+        let isr_shared = Arc::clone(&shared);
+        let subscription = driver.subscribe(move |interrupt: TouchInterrupt| {
+            isr_shared
+                .touched
+                .fetch_or(interrupt.status_mask.bits(), Ordering::AcqRel);
+            let _ = producer.push(interrupt);
+            isr_shared.waker.wake();
+        })?;
+        let intr_was_enabled = driver.is_intr_enabled();
+        driver.intr_enable()?;
+        Ok(AsyncTouch {
+            driver,
+            shared,
+            events,
+            ticker: None,
+            intr_was_enabled,
+            _subscription: subscription,
+        })
+    }
+
+    pub fn driver(&self) -> &'d TouchDriver<H> {
+        self.driver
+    }
+
+    /// Have the ticker wake the futures until the returned guard is dropped, starting it if
+    /// needed.
+    fn poll_periodically(&mut self) -> Result<Polling, TouchError> {
+        if self.ticker.is_none() {
+            self.ticker = Some(Ticker::start(Arc::clone(&self.shared), POLL_PERIOD)?);
+        }
+        self.shared.polling.store(true, Ordering::Release);
+        if let Some(ticker) = &self.ticker {
+            ticker.unpark();
+        }
+        Ok(Polling(Arc::clone(&self.shared)))
+    }
+
+    /// Wait until one of `channels` is touched and return the touched ones. Touches reported
+    /// before the call are ignored.
+    pub async fn wait_for_touch(&mut self, channels: ChannelSet) -> ChannelSet {
+        let shared = &*self.shared;
+        shared.take_touched(channels);
+        poll_fn(|cx| {
+            shared.waker.register(cx.waker());
+            let touched = shared.take_touched(channels);
+            if touched.is_empty() {
+                Poll::Pending
+            } else {
+                Poll::Ready(touched)
+            }
+        })
+        .await
+    }
+
+    /// Wait until none of `channels` is touched, that is until the last counter value of each
+    /// one is back on the untouched side of its threshold, for the current trigger mode.
+    ///
+    /// The hardware only interrupts on touched pads, so the values are checked every
+    /// `POLL_PERIOD`. The trigger mode is left alone, so the other subscribers are not affected.
+    ///
+    /// # Errors
+    ///
+    /// * `TouchError::Spawn` the ticker thread could not be spawned
+    /// * errors of `TouchPad::read_raw_data`, e.g. for a channel that is not enabled
+    pub async fn wait_for_release(&mut self, channels: ChannelSet) -> Result<(), TouchError> {
+        let _polling = self.poll_periodically()?;
+        let driver = self.driver;
+        let shared = &*self.shared;
+        poll_fn(|cx| {
+            shared.waker.register(cx.waker());
+            let trigger_mode = driver.get_trigger_mode()?;
+            for touch_num in channels {
+                let raw = driver.read_raw_data(touch_num)?;
+                let threshold = driver.get_thresh(touch_num)?;
+                let touched = match trigger_mode {
+                    TouchTriggerMode::Above => raw > threshold,
+                    _ => raw < threshold,
+                };
+                if touched {
+                    return Poll::Pending;
+                }
+            }
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+
+    /// Trigger a measurement with `sw_start` and wait for `meas_is_done`. The FSM is switched to
+    /// SW mode first if needed.
+    ///
+    /// The end of a measurement raises no interrupt, so the flag is checked every
+    /// `POLL_PERIOD`.
+    ///
+    /// # Errors
+    ///
+    /// * `TouchError::Spawn` the ticker thread could not be spawned
+    pub async fn measure(&mut self) -> Result<(), TouchError> {
+        if self.driver.get_fsm_mode()? != TouchFSMMode::SW {
+            self.driver.set_fsm_mode(TouchFSMMode::SW)?;
+        }
+        let _polling = self.poll_periodically()?;
+        self.driver.sw_start()?;
+        let driver = self.driver;
+        let shared = &*self.shared;
+        poll_fn(|cx| {
+            shared.waker.register(cx.waker());
+            if driver.meas_is_done() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Stream of the touch interrupts. Interrupts received before the call are dropped, and
+    /// interrupts are dropped when the stream is not polled fast enough (see `overflows`).
+    pub fn events(&mut self) -> TouchEvents<'_, 'd, H> {
+        while self.events.try_recv().is_some() {}
+        TouchEvents { touch: self }
+    }
+
+    /// Number of interrupts dropped because the event stream was not polled fast enough.
+    pub fn overflows(&self) -> u32 {
+        self.events.overflows()
+    }
+}
+
+impl<H: TouchHal + Sync> Drop for AsyncTouch<'_, H> {
+    fn drop(&mut self) {
+        if !self.intr_was_enabled {
+            let _ = self.driver.intr_disable();
+        }
+    }
+}
+
+/// Stops the periodic wakeups when the future that asked for them ends.
+struct Polling(Arc<Shared>);
+
+impl Drop for Polling {
+    fn drop(&mut self) {
+        self.0.polling.store(false, Ordering::Release);
+    }
+}
+
+/// Thread waking the futures every period while `Shared::polling` is set, and sleeping
+/// otherwise. It is stopped and joined when dropped.
+struct Ticker {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Ticker {
+    fn start(shared: Arc<Shared>, period: Duration) -> Result<Self, TouchError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name("touch-ticker".into())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Acquire) {
+                    if shared.polling.load(Ordering::Acquire) {
+                        thread::park_timeout(period);
+                        shared.waker.wake();
+                    } else {
+                        thread::park();
+                    }
+                }
+            })
+            .map_err(TouchError::Spawn)?;
+        Ok(Ticker {
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    fn unpark(&self) {
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Stream of touch interrupts, from `AsyncTouch::events`.
+pub struct TouchEvents<'a, 'd, H: TouchHal + Sync> {
+    touch: &'a mut AsyncTouch<'d, H>,
+}
+
+impl<H: TouchHal + Sync> Stream for TouchEvents<'_, '_, H> {
+    type Item = TouchInterrupt;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let touch = &mut *self.get_mut().touch;
+        touch.shared.waker.register(cx.waker());
+        match touch.events.try_recv() {
+            Some(interrupt) => Poll::Ready(Some(interrupt)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::Mutex;
+    use std::task::{Wake, Waker};
+    use std::thread::Thread;
+
+    struct ChannelWaker(Mutex<Sender<()>>);
+
+    impl Wake for ChannelWaker {
+        fn wake(self: Arc<Self>) {
+            let _ = self.0.lock().unwrap().send(());
+        }
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, parking the thread until the future is woken.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    /// Driver in timer mode with `Num3` configured at a threshold of 500 and untouched.
+    fn timer_driver(sim: &SimTouchHal) -> TouchDriver<SimTouchHal> {
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        driver
+            .take_pad(TouchPadChannel::Num3)
+            .unwrap()
+            .configure(500)
+            .unwrap();
+        driver.set_fsm_mode(TouchFSMMode::Timer).unwrap();
+        sim.set_raw(TouchPadChannel::Num3, 1000);
+        driver
+    }
+
+    /// Run `action` on another thread after a short delay, while the caller blocks.
+    fn later(action: impl FnOnce() + Send + 'static) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            action();
+        })
+    }
+
+    #[test]
+    fn wait_for_touch_is_woken_by_the_isr() {
+        let sim = SimTouchHal::new();
+        let driver = timer_driver(&sim);
+        let mut touch = AsyncTouch::new(&driver).unwrap();
+        let channels = ChannelSet::from(TouchPadChannel::Num3);
+
+        let sim_thread = sim.clone();
+        let toucher = later(move || {
+            sim_thread.set_raw(TouchPadChannel::Num3, 100);
+            sim_thread.tick();
+        });
+        assert_eq!(block_on(touch.wait_for_touch(channels)), channels);
+        toucher.join().unwrap();
+    }
+
+    #[test]
+    fn wait_for_release_keeps_the_trigger_mode() {
+        let sim = SimTouchHal::new();
+        let driver = timer_driver(&sim);
+        sim.set_raw(TouchPadChannel::Num3, 100);
+        let mut touch = AsyncTouch::new(&driver).unwrap();
+        let channels = ChannelSet::from(TouchPadChannel::Num3);
+
+        let sim_thread = sim.clone();
+        let releaser = later(move || {
+            assert_eq!(
+                sim_thread.get_trigger_mode().unwrap(),
+                TouchTriggerMode::Below
+            );
+            sim_thread.set_raw(TouchPadChannel::Num3, 1000);
+        });
+        block_on(touch.wait_for_release(channels)).unwrap();
+        releaser.join().unwrap();
+        assert_eq!(driver.get_trigger_mode().unwrap(), TouchTriggerMode::Below);
+
+        // Already released.
+        block_on(touch.wait_for_release(channels)).unwrap();
+    }
+
+    #[test]
+    fn events_stream_the_interrupts() {
+        let sim = SimTouchHal::new();
+        let driver = timer_driver(&sim);
+        let mut touch = AsyncTouch::new(&driver).unwrap();
+        sim.set_raw(TouchPadChannel::Num3, 100);
+        sim.tick();
+
+        // The interrupt before `events` is dropped.
+        let mut events = touch.events();
+        let sim_thread = sim.clone();
+        let ticker = later(move || {
+            sim_thread.advance_ms(5);
+            sim_thread.tick();
+        });
+        let interrupt = block_on(poll_fn(|cx| Pin::new(&mut events).poll_next(cx))).unwrap();
+        ticker.join().unwrap();
+        assert_eq!(
+            interrupt.status_mask,
+            ChannelSet::from(TouchPadChannel::Num3)
+        );
+        assert_eq!(interrupt.timestamp, 5);
+        assert_eq!(touch.overflows(), 0);
+    }
+
+    #[test]
+    fn measure_reuses_the_ticker() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let mut touch = AsyncTouch::new(&driver).unwrap();
+        let ticker_thread = |touch: &AsyncTouch<'_, SimTouchHal>| {
+            let ticker = touch.ticker.as_ref().unwrap();
+            ticker.thread.as_ref().unwrap().thread().id()
+        };
+
+        block_on(touch.measure()).unwrap();
+        let first = ticker_thread(&touch);
+        assert!(!touch.shared.polling.load(Ordering::Acquire));
+        block_on(touch.measure()).unwrap();
+        assert_eq!(ticker_thread(&touch), first);
+    }
+
+    #[test]
+    fn measure_is_woken_by_the_ticker() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let mut touch = AsyncTouch::new(&driver).unwrap();
+        let (sender, woken) = channel();
+        let waker = Waker::from(Arc::new(ChannelWaker(Mutex::new(sender))));
+        let mut cx = Context::from_waker(&waker);
+
+        sim.set_stalled(true);
+        let mut measure = Box::pin(touch.measure());
+        for _ in 0..3 {
+            assert!(measure.as_mut().poll(&mut cx).is_pending());
+            woken.recv_timeout(Duration::from_secs(1)).unwrap();
+        }
+        drop(measure);
+
+        sim.set_stalled(false);
+        let mut measure = Box::pin(touch.measure());
+        assert!(matches!(
+            measure.as_mut().poll(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+    }
+
+    #[test]
+    fn drop_restores_the_interrupt_enable() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        drop(AsyncTouch::new(&driver).unwrap());
+        assert!(!sim.is_intr_enabled());
+        assert!(!driver.is_intr_enabled());
+
+        driver.intr_enable().unwrap();
+        drop(AsyncTouch::new(&driver).unwrap());
+        assert!(sim.is_intr_enabled());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::hal::TouchHal;
+use crate::pad::TouchPad;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Fractional bits of the fixed point baseline, so slow drift is not lost to integer rounding.
+const FRAC_BITS: u32 = 8;
+
+/// Largest `BaselineConfig::drift_shift`. The fixed point baseline fits in 32 bits, so a larger
+/// shift would only freeze it.
+pub const MAX_DRIFT_SHIFT: u8 = 31;
+
+/// Tuning of the `BaselineTracker`. All durations are counted in samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaselineConfig {
+    /// Delta (baseline - raw) from which a channel counts as touched, must be positive. The
+    /// baseline is frozen while a channel is touched.
+    pub touch_delta: u16,
+    /// Speed of the drift compensation: every untouched sample moves the baseline by
+    /// `1 / 2^drift_shift` of the distance to the reading. At most `MAX_DRIFT_SHIFT`.
+    pub drift_shift: u8,
+    /// Number of consecutive touched samples after which the baseline is re-seeded from the
+    /// reading, so an object left on a pad does not block it forever. 0 disables re-seeding.
+    pub reseed_samples: u32,
+    /// Number of consecutive samples at least `touch_delta` above the baseline after which it is
+    /// re-seeded from the reading, the baseline having been taken while the pad was touched.
+    /// The baseline is frozen until then, so a single spike does not move it. At least 1.
+    pub release_reseed_samples: u32,
+}
+
+impl Default for BaselineConfig {
+    fn default() -> Self {
+        BaselineConfig {
+            touch_delta: 50,
+            drift_shift: 6,
+            reseed_samples: 1000,
+            release_reseed_samples: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ChannelBaseline {
+    /// Baseline in fixed point, `None` until the first sample.
+    baseline: Option<u32>,
+    raw: u16,
+    touched_samples: u32,
+    /// Consecutive samples far above the baseline.
+    released_samples: u32,
+}
+
+impl ChannelBaseline {
+    fn baseline(&self) -> Option<u16> {
+        self.baseline.map(|baseline| (baseline >> FRAC_BITS) as u16)
+    }
+
+    fn delta(&self) -> i32 {
+        self.baseline()
+            .map_or(0, |baseline| baseline as i32 - self.raw as i32)
+    }
+
+    fn seed(&mut self, raw: u16) {
+        self.baseline = Some((raw as u32) << FRAC_BITS);
+        self.touched_samples = 0;
+        self.released_samples = 0;
+    }
+}
+
+/// Software baseline of the untouched counter value of each channel, fed with `read_raw_data`
+/// samples. The baseline slowly follows temperature and humidity drift, is frozen while a touch
+/// is active and re-seeded after a touch that lasts too long.
+///
+/// The tracker only sees samples, so it can be driven from the pads (`sample`) or from recorded
+/// or synthetic data (`update`).
+#[derive(Debug, Clone)]
+pub struct BaselineTracker {
+    config: BaselineConfig,
+    channels: [ChannelBaseline; TouchPadChannel::ALL.len()],
+}
+
+impl BaselineTracker {
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG `touch_delta` or `release_reseed_samples` is 0, or `drift_shift` is
+    ///   over `MAX_DRIFT_SHIFT`
+    pub fn new(config: BaselineConfig) -> Result<Self, EspErr> {
+        if config.touch_delta == 0
+            || config.release_reseed_samples == 0
+            || config.drift_shift > MAX_DRIFT_SHIFT
+        {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        Ok(BaselineTracker {
+            config,
+            channels: [ChannelBaseline::default(); TouchPadChannel::ALL.len()],
+        })
+    }
+
+    pub fn config(&self) -> &BaselineConfig {
+        &self.config
+    }
+
+    /// Feed one raw sample of a channel and return its new delta. The first sample seeds the
+    /// baseline. A reading of 0 (broken connection) is ignored.
+    pub fn update(&mut self, touch_num: TouchPadChannel, raw: u16) -> i32 {
+        let config = self.config;
+        let Some(channel) = self.channels.get_mut(touch_num as usize) else {
+            return 0;
+        };
+        if raw == 0 {
+            return channel.delta();
+        }
+        channel.raw = raw;
+        let Some(baseline) = channel.baseline else {
+            channel.seed(raw);
+            return 0;
+        };
+
+        let delta = channel.delta();
+        if delta >= config.touch_delta as i32 {
+            channel.touched_samples += 1;
+            channel.released_samples = 0;
+            if config.reseed_samples != 0 && channel.touched_samples >= config.reseed_samples {
+                channel.seed(raw);
+            }
+        } else if delta <= -(config.touch_delta as i32) {
+            // Far above the baseline: the baseline was taken while the pad was touched.
+            channel.touched_samples = 0;
+            channel.released_samples += 1;
+            if channel.released_samples >= config.release_reseed_samples {
+                channel.seed(raw);
+            }
+        } else {
+            channel.touched_samples = 0;
+            channel.released_samples = 0;
+            let target = ((raw as u32) << FRAC_BITS) as i64;
+            // Division rounds toward zero, so the baseline drifts as fast down as up.
+            let step = (target - baseline as i64) / (1 << config.drift_shift);
+            channel.baseline = Some((baseline as i64 + step) as u32);
+        }
+        channel.delta()
+    }
+
+    /// Read the raw counter value of a pad and feed it to the tracker.
+    pub fn sample<H: TouchHal>(&mut self, pad: &TouchPad<'_, H>) -> Result<i32, TouchError> {
+        let raw = pad.read_raw_data()?;
+        Ok(self.update(pad.channel(), raw))
+    }
+
+    /// Current baseline of a channel, `None` before its first sample.
+    pub fn baseline(&self, touch_num: TouchPadChannel) -> Option<u16> {
+        self.channels
+            .get(touch_num as usize)
+            .and_then(ChannelBaseline::baseline)
+    }
+
+    /// Seed the baseline of a channel with a known untouched value, e.g. a saved calibration.
+    pub fn set_baseline(&mut self, touch_num: TouchPadChannel, baseline: u16) {
+        if let Some(channel) = self.channels.get_mut(touch_num as usize) {
+            channel.seed(baseline);
+            channel.raw = baseline;
+        }
+    }
+
+    /// Signed difference baseline - raw of the last sample. Positive when the pad is touched.
+    pub fn delta(&self, touch_num: TouchPadChannel) -> i32 {
+        self.channels
+            .get(touch_num as usize)
+            .map_or(0, ChannelBaseline::delta)
+    }
+
+    /// Deltas of every channel, indexed by `TouchPadChannel`.
+    pub fn deltas(&self) -> [i32; TouchPadChannel::ALL.len()] {
+        self.channels.map(|channel| channel.delta())
+    }
+
+    /// Whether the last sample of a channel was over the touch delta.
+    pub fn is_touched(&self, touch_num: TouchPadChannel) -> bool {
+        self.delta(touch_num) >= self.config.touch_delta as i32
+    }
+
+    /// Forget the baseline of a channel, the next sample seeds it again.
+    pub fn reset(&mut self, touch_num: TouchPadChannel) {
+        if let Some(channel) = self.channels.get_mut(touch_num as usize) {
+            *channel = ChannelBaseline::default();
+        }
+    }
+}
+
+impl Default for BaselineTracker {
+    fn default() -> Self {
+        BaselineTracker {
+            config: BaselineConfig::default(),
+            channels: [ChannelBaseline::default(); TouchPadChannel::ALL.len()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_shift_is_checked() {
+        let config = |drift_shift| BaselineConfig {
+            drift_shift,
+            ..BaselineConfig::default()
+        };
+        assert!(BaselineTracker::new(config(MAX_DRIFT_SHIFT)).is_ok());
+        assert_eq!(
+            BaselineTracker::new(config(MAX_DRIFT_SHIFT + 1)).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(BaselineTracker::new(config(u8::MAX)).is_err());
+
+        let mut tracker = BaselineTracker::new(config(MAX_DRIFT_SHIFT)).unwrap();
+        tracker.update(TouchPadChannel::Num0, 1000);
+        tracker.update(TouchPadChannel::Num0, 990);
+        assert_eq!(tracker.baseline(TouchPadChannel::Num0), Some(1000));
+    }
+
+    #[test]
+    fn zero_settings_are_rejected() {
+        let zero_delta = BaselineConfig {
+            touch_delta: 0,
+            ..BaselineConfig::default()
+        };
+        assert!(BaselineTracker::new(zero_delta).is_err());
+        let zero_release = BaselineConfig {
+            release_reseed_samples: 0,
+            ..BaselineConfig::default()
+        };
+        assert!(BaselineTracker::new(zero_release).is_err());
+    }
+
+    fn tracker() -> BaselineTracker {
+        BaselineTracker::new(BaselineConfig {
+            touch_delta: 50,
+            drift_shift: 2,
+            reseed_samples: 20,
+            release_reseed_samples: 3,
+        })
+        .unwrap()
+    }
+
+    const PAD: TouchPadChannel = TouchPadChannel::Num4;
+
+    #[test]
+    fn slow_drift_is_tracked_both_ways() {
+        let mut up = tracker();
+        let mut down = tracker();
+        up.update(PAD, 1000);
+        down.update(PAD, 1000);
+        for _ in 0..4 {
+            up.update(PAD, 1040);
+            down.update(PAD, 960);
+        }
+        assert!(up.baseline(PAD).unwrap() > 1020);
+        // Compared in fixed point, reading the baseline truncates the fractions.
+        let fixed = |tracker: &BaselineTracker| tracker.channels[PAD as usize].baseline.unwrap();
+        assert_eq!(
+            fixed(&up) - (1000 << FRAC_BITS),
+            (1000 << FRAC_BITS) - fixed(&down)
+        );
+
+        for _ in 0..100 {
+            up.update(PAD, 1040);
+        }
+        // The last fractions of a count are not made up, the steps rounding toward zero.
+        assert!((1039..=1040).contains(&up.baseline(PAD).unwrap()));
+        assert!(up.delta(PAD).abs() <= 1);
+    }
+
+    #[test]
+    fn baseline_is_frozen_while_touched() {
+        let mut tracker = tracker();
+        tracker.update(PAD, 1000);
+        for _ in 0..19 {
+            assert_eq!(tracker.update(PAD, 900), 100);
+            assert!(tracker.is_touched(PAD));
+        }
+        assert_eq!(tracker.baseline(PAD), Some(1000));
+        // A touch lasting `reseed_samples` re-seeds the baseline.
+        assert_eq!(tracker.update(PAD, 900), 0);
+        assert_eq!(tracker.baseline(PAD), Some(900));
+    }
+
+    #[test]
+    fn spikes_above_the_baseline_do_not_reseed() {
+        let mut tracker = tracker();
+        tracker.update(PAD, 1000);
+        tracker.update(PAD, 1200);
+        tracker.update(PAD, 1200);
+        tracker.update(PAD, 1000);
+        tracker.update(PAD, 1200);
+        assert_eq!(tracker.baseline(PAD), Some(1000));
+
+        // Seeded while touched: the pad reads much higher once released.
+        tracker.update(PAD, 1200);
+        tracker.update(PAD, 1200);
+        assert_eq!(tracker.baseline(PAD), Some(1200));
+    }
+}
@@ -0,0 +1,349 @@
+use crate::hal::TouchHal;
+use crate::pad::TouchPad;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Events emitted by a `TouchButton`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// The button is pressed (after debounce).
+    Pressed,
+    /// The button is released (after debounce).
+    Released,
+    /// The button is held for `long_press_ms`.
+    LongPress,
+    /// The button is still held, every `repeat_interval_ms` after the long press.
+    Repeat,
+    /// A press released before the long press delay.
+    Click,
+    /// A second click whose press started less than `double_click_ms` after the first click.
+    /// It follows the `Click` of that second press.
+    DoubleClick,
+}
+
+/// Tuning of a `TouchButton`. Times are in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonConfig {
+    /// Side of the thresholds on which the pad counts as touched, as for the hardware interrupt.
+    pub trigger_mode: TouchTriggerMode,
+    /// Samples past this value count as a touch.
+    pub touch_threshold: u16,
+    /// Samples have to get back past this value to count as a release. Between the two
+    /// thresholds the previous state is kept. It must not be on the touched side of
+    /// `touch_threshold`, or a sample could count as both and the button would oscillate.
+    pub release_threshold: u16,
+    /// Number of consecutive samples needed to accept a press or a release.
+    pub debounce_samples: u32,
+    /// Hold time before `LongPress`, 0 disables it.
+    pub long_press_ms: u32,
+    /// Period of `Repeat` after the long press, 0 disables it.
+    pub repeat_interval_ms: u32,
+    /// Maximum time between a click and the next press for a `DoubleClick`, 0 disables it.
+    pub double_click_ms: u32,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        ButtonConfig {
+            trigger_mode: TouchTriggerMode::Below,
+            touch_threshold: 600,
+            release_threshold: 700,
+            debounce_samples: 3,
+            long_press_ms: 800,
+            repeat_interval_ms: 200,
+            double_click_ms: 300,
+        }
+    }
+}
+
+/// Debounced button state machine on top of the samples of one channel. It is driven by
+/// `(timestamp, sample)` pairs, so its timing only depends on the timestamps it is given.
+///
+/// The button counts as held until a release is accepted, so the long press and repeat timers
+/// keep running while the release is being debounced.
+#[derive(Debug, Clone)]
+pub struct TouchButton {
+    config: ButtonConfig,
+    pressed: bool,
+    /// Consecutive samples disagreeing with `pressed`.
+    pending: u32,
+    press_start_ms: u32,
+    long_pressed: bool,
+    next_repeat_ms: u32,
+    /// Time of the last click that can still become a double click.
+    last_click_ms: Option<u32>,
+    events: Vec<ButtonEvent>,
+}
+
+impl TouchButton {
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG a `Max` trigger mode, or a `release_threshold` on the touched side
+    ///   of `touch_threshold`
+    pub fn new(config: ButtonConfig) -> Result<Self, EspErr> {
+        let ordered = match config.trigger_mode {
+            TouchTriggerMode::Below => config.touch_threshold <= config.release_threshold,
+            TouchTriggerMode::Above => config.touch_threshold >= config.release_threshold,
+            TouchTriggerMode::Max => false,
+        };
+        if !ordered {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        Ok(Self::with_config(config))
+    }
+
+    fn with_config(config: ButtonConfig) -> Self {
+        TouchButton {
+            config,
+            pressed: false,
+            pending: 0,
+            press_start_ms: 0,
+            long_pressed: false,
+            next_repeat_ms: 0,
+            last_click_ms: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ButtonConfig {
+        &self.config
+    }
+
+    /// Whether the button is currently pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    fn is_touch(&self, sample: u16) -> bool {
+        match self.config.trigger_mode {
+            TouchTriggerMode::Above => sample > self.config.touch_threshold,
+            _ => sample < self.config.touch_threshold,
+        }
+    }
+
+    fn is_release(&self, sample: u16) -> bool {
+        match self.config.trigger_mode {
+            TouchTriggerMode::Above => sample < self.config.release_threshold,
+            _ => sample > self.config.release_threshold,
+        }
+    }
+
+    /// Feed one sample taken at `timestamp_ms` and return the events it caused.
+    pub fn update(&mut self, timestamp_ms: u32, sample: u16) -> std::vec::Drain<'_, ButtonEvent> {
+        self.events.clear();
+
+        let changing = if self.pressed {
+            self.is_release(sample)
+        } else {
+            self.is_touch(sample)
+        };
+        self.pending = if changing { self.pending + 1 } else { 0 };
+        if self.pending >= self.config.debounce_samples.max(1) {
+            self.pending = 0;
+            if self.pressed {
+                self.release(timestamp_ms);
+            } else {
+                self.press(timestamp_ms);
+            }
+        } else if self.pressed {
+            self.hold(timestamp_ms);
+        }
+
+        self.events.drain(..)
+    }
+
+    /// Read the pad and feed the sample.
+    pub fn poll<H: TouchHal>(
+        &mut self,
+        pad: &TouchPad<'_, H>,
+        timestamp_ms: u32,
+    ) -> Result<std::vec::Drain<'_, ButtonEvent>, TouchError> {
+        let sample = pad.read()?;
+        Ok(self.update(timestamp_ms, sample))
+    }
+
+    fn press(&mut self, timestamp_ms: u32) {
+        self.pressed = true;
+        self.press_start_ms = timestamp_ms;
+        self.long_pressed = false;
+        self.events.push(ButtonEvent::Pressed);
+    }
+
+    fn hold(&mut self, timestamp_ms: u32) {
+        let held_ms = timestamp_ms.wrapping_sub(self.press_start_ms);
+        if !self.long_pressed {
+            if self.config.long_press_ms != 0 && held_ms >= self.config.long_press_ms {
+                self.long_pressed = true;
+                self.last_click_ms = None;
+                self.next_repeat_ms = timestamp_ms.wrapping_add(self.config.repeat_interval_ms);
+                self.events.push(ButtonEvent::LongPress);
+            }
+        } else if self.config.repeat_interval_ms != 0
+            && timestamp_ms.wrapping_sub(self.next_repeat_ms) as i32 >= 0
+        {
+            self.next_repeat_ms = self
+                .next_repeat_ms
+                .wrapping_add(self.config.repeat_interval_ms);
+            self.events.push(ButtonEvent::Repeat);
+        }
+    }
+
+    fn release(&mut self, timestamp_ms: u32) {
+        self.pressed = false;
+        self.events.push(ButtonEvent::Released);
+        if self.long_pressed {
+            return;
+        }
+        self.events.push(ButtonEvent::Click);
+        let double = self.last_click_ms.is_some_and(|last_click_ms| {
+            self.press_start_ms.wrapping_sub(last_click_ms) <= self.config.double_click_ms
+        });
+        if double {
+            self.events.push(ButtonEvent::DoubleClick);
+            self.last_click_ms = None;
+        } else if self.config.double_click_ms != 0 {
+            self.last_click_ms = Some(timestamp_ms);
+        }
+    }
+}
+
+impl Default for TouchButton {
+    fn default() -> Self {
+        Self::with_config(ButtonConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ButtonEvent::*;
+
+    /// Button with a debounce of 2 samples, fed with `(timestamp, sample)` pairs. Every sample
+    /// is 10 ms apart in the streams below.
+    fn button() -> TouchButton {
+        TouchButton::new(ButtonConfig {
+            debounce_samples: 2,
+            ..ButtonConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn feed(button: &mut TouchButton, stream: &[(u32, u16)]) -> Vec<(u32, ButtonEvent)> {
+        let mut events = Vec::new();
+        for &(timestamp_ms, sample) in stream {
+            events.extend(
+                button
+                    .update(timestamp_ms, sample)
+                    .map(|event| (timestamp_ms, event)),
+            );
+        }
+        events
+    }
+
+    #[test]
+    fn press_and_release_are_debounced() {
+        let mut button = button();
+        let events = feed(
+            &mut button,
+            &[
+                (0, 800),
+                (10, 500),
+                (20, 800),
+                (30, 500),
+                (40, 500),
+                (50, 650),
+            ],
+        );
+        assert_eq!(events, vec![(40, Pressed)]);
+        assert!(button.is_pressed());
+
+        // 650 is between the thresholds, so a single spike above 700 does not release.
+        let events = feed(&mut button, &[(60, 750), (70, 650), (80, 750), (90, 750)]);
+        assert_eq!(events, vec![(90, Released), (90, Click)]);
+        assert!(!button.is_pressed());
+    }
+
+    #[test]
+    fn long_press_and_repeat() {
+        let mut button = button();
+        let mut stream = vec![(0, 500), (10, 500)];
+        stream.extend((2..=130).map(|index| (index * 10, 500)));
+        stream.extend([(1310, 800), (1320, 800)]);
+        let events = feed(&mut button, &stream);
+        assert_eq!(
+            events,
+            vec![
+                (10, Pressed),
+                (810, LongPress),
+                (1010, Repeat),
+                (1210, Repeat),
+                (1320, Released),
+            ]
+        );
+    }
+
+    #[test]
+    fn hold_counts_while_the_release_is_debounced() {
+        let mut button = button();
+        let mut stream = vec![(0, 500), (10, 500), (800, 500)];
+        // The first release sample is at the long press time, before the release is accepted.
+        stream.extend([(810, 800), (820, 800)]);
+        let events = feed(&mut button, &stream);
+        assert_eq!(
+            events,
+            vec![(10, Pressed), (810, LongPress), (820, Released)]
+        );
+    }
+
+    #[test]
+    fn click_and_double_click() {
+        let mut button = button();
+        let click = |start: u32| {
+            [
+                (start, 500),
+                (start + 10, 500),
+                (start + 20, 800),
+                (start + 30, 800),
+            ]
+        };
+        let mut stream = Vec::new();
+        stream.extend(click(0));
+        stream.extend(click(200));
+        // Too late for a double click.
+        stream.extend(click(1000));
+        let events = feed(&mut button, &stream);
+        assert_eq!(
+            events,
+            vec![
+                (10, Pressed),
+                (30, Released),
+                (30, Click),
+                (210, Pressed),
+                (230, Released),
+                (230, Click),
+                (230, DoubleClick),
+                (1010, Pressed),
+                (1030, Released),
+                (1030, Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn thresholds_must_be_ordered() {
+        let config = |trigger_mode, touch_threshold, release_threshold| ButtonConfig {
+            trigger_mode,
+            touch_threshold,
+            release_threshold,
+            ..ButtonConfig::default()
+        };
+        assert!(TouchButton::new(config(TouchTriggerMode::Below, 600, 600)).is_ok());
+        assert_eq!(
+            TouchButton::new(config(TouchTriggerMode::Below, 700, 600)).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(TouchButton::new(config(TouchTriggerMode::Above, 700, 600)).is_ok());
+        assert!(TouchButton::new(config(TouchTriggerMode::Above, 600, 700)).is_err());
+        assert!(TouchButton::new(config(TouchTriggerMode::Max, 600, 700)).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::driver::TouchDriver;
+use crate::hal::TouchHal;
+use crate::pad::TouchPad;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::thread;
+use std::time::Duration;
+
+/// How the threshold is derived from the untouched mean and noise of a channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdStrategy {
+    /// Threshold `ratio * mean` away from the mean, e.g. 0.3 puts it at 70% of the untouched
+    /// value in `TouchTriggerMode::Below`.
+    SensitivityRatio(f32),
+    /// Threshold `snr * noise` away from the mean, noise being the standard deviation.
+    TargetSnr(f32),
+}
+
+/// Parameters of `calibrate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationConfig {
+    /// Number of samples taken on each channel. The pads must not be touched during the
+    /// `samples * sample_interval` calibration time.
+    pub samples: u32,
+    /// Time between two samples.
+    pub sample_interval: Duration,
+    pub strategy: ThresholdStrategy,
+    /// Channels whose noise is above this fraction of the mean are flagged `HighNoise`.
+    pub max_noise_ratio: f32,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        CalibrationConfig {
+            samples: 50,
+            sample_interval: Duration::from_millis(10),
+            strategy: ThresholdStrategy::SensitivityRatio(0.3),
+            max_noise_ratio: 0.02,
+        }
+    }
+}
+
+/// Outcome of the calibration of one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStatus {
+    /// The threshold was applied.
+    Ok,
+    /// The channel is too noisy for the requested sensitivity, its threshold was left untouched.
+    HighNoise,
+    /// The channel reads 0 (`TouchError::BadConnection`), its hardware connection is broken.
+    BadConnection,
+}
+
+/// Calibration report of one channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelCalibration {
+    pub channel: TouchPadChannel,
+    /// Mean untouched counter value.
+    pub mean: f32,
+    /// Standard deviation of the untouched counter value.
+    pub noise: f32,
+    /// Derived threshold, applied only when `status` is `Ok`.
+    pub threshold: u16,
+    pub status: CalibrationStatus,
+}
+
+#[derive(Default)]
+struct Samples {
+    count: u32,
+    sum: f64,
+    sum_sq: f64,
+    bad_connection: bool,
+}
+
+/// Sample every enabled pad with no touch, derive its threshold from the mean and noise and
+/// apply it with `set_thresh`, on the side of the mean given by the current `TouchTriggerMode`.
+/// Pads whose channel is not in the enable mask are skipped.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG a pad was taken from another driver
+pub fn calibrate<H: TouchHal>(
+    driver: &TouchDriver<H>,
+    pads: &[TouchPad<'_, H>],
+    config: &CalibrationConfig,
+) -> Result<Vec<ChannelCalibration>, TouchError> {
+    if let Some(pad) = pads.iter().find(|pad| !std::ptr::eq(pad.driver(), driver)) {
+        return Err(TouchError::esp(EspErr::EspErrInvalidArg, "calibrate").on(pad.channel()));
+    }
+    let trigger_mode = driver.get_trigger_mode()?;
+    let en_mask = driver.get_group_mask()?.en_mask;
+    let pads: Vec<&TouchPad<'_, H>> = pads
+        .iter()
+        .filter(|pad| en_mask.contains(pad.channel()))
+        .collect();
+
+    let mut samples: Vec<Samples> = pads.iter().map(|_| Samples::default()).collect();
+    for round in 0..config.samples.max(1) {
+        if round != 0 {
+            thread::sleep(config.sample_interval);
+        }
+        for (pad, samples) in pads.iter().zip(samples.iter_mut()) {
+            match pad.read() {
+                Ok(0) | Err(TouchError::BadConnection(_)) => samples.bad_connection = true,
+                Ok(value) => {
+                    samples.count += 1;
+                    samples.sum += value as f64;
+                    samples.sum_sq += value as f64 * value as f64;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    let mut report = Vec::with_capacity(pads.len());
+    for (pad, samples) in pads.iter().zip(samples.iter()) {
+        let mut calibration = ChannelCalibration {
+            channel: pad.channel(),
+            mean: 0.0,
+            noise: 0.0,
+            threshold: 0,
+            status: CalibrationStatus::BadConnection,
+        };
+        if samples.bad_connection || samples.count == 0 {
+            report.push(calibration);
+            continue;
+        }
+        let mean = samples.sum / samples.count as f64;
+        let variance = (samples.sum_sq / samples.count as f64 - mean * mean).max(0.0);
+        calibration.mean = mean as f32;
+        calibration.noise = variance.sqrt() as f32;
+
+        let margin = match config.strategy {
+            ThresholdStrategy::SensitivityRatio(ratio) => ratio * calibration.mean,
+            ThresholdStrategy::TargetSnr(snr) => snr * calibration.noise,
+        }
+        .max(1.0);
+        let threshold = match trigger_mode {
+            TouchTriggerMode::Above => calibration.mean + margin,
+            _ => calibration.mean - margin,
+        };
+        calibration.threshold = threshold.round().clamp(0.0, u16::MAX as f32) as u16;
+
+        let too_noisy = calibration.noise > config.max_noise_ratio * calibration.mean
+            || margin <= calibration.noise
+            || threshold <= 0.0
+            || threshold >= u16::MAX as f32;
+        calibration.status = if too_noisy {
+            CalibrationStatus::HighNoise
+        } else {
+            pad.set_thresh(calibration.threshold)?;
+            CalibrationStatus::Ok
+        };
+        report.push(calibration);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+
+    const CONFIG: CalibrationConfig = CalibrationConfig {
+        samples: 10,
+        sample_interval: Duration::ZERO,
+        strategy: ThresholdStrategy::SensitivityRatio(0.3),
+        max_noise_ratio: 0.02,
+    };
+
+    fn driver(sim: &SimTouchHal) -> TouchDriver<SimTouchHal> {
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        for pad in driver.take_pads().unwrap().iter().take(3) {
+            pad.configure(1).unwrap();
+        }
+        driver
+    }
+
+    #[test]
+    fn threshold_follows_the_trigger_mode() {
+        let sim = SimTouchHal::new();
+        let driver = driver(&sim);
+        let pads = [driver.take_pad(TouchPadChannel::Num1).unwrap()];
+        sim.set_noise(TouchPadChannel::Num1, 5);
+
+        let report = calibrate(&driver, &pads, &CONFIG).unwrap();
+        assert_eq!(
+            report,
+            vec![ChannelCalibration {
+                channel: TouchPadChannel::Num1,
+                mean: 1000.0,
+                noise: 5.0,
+                threshold: 700,
+                status: CalibrationStatus::Ok,
+            }]
+        );
+        assert_eq!(pads[0].get_thresh(), Ok(700));
+
+        driver.set_trigger_mode(TouchTriggerMode::Above).unwrap();
+        let report = calibrate(&driver, &pads, &CONFIG).unwrap();
+        assert_eq!(report[0].threshold, 1300);
+        assert_eq!(pads[0].get_thresh(), Ok(1300));
+
+        let config = CalibrationConfig {
+            strategy: ThresholdStrategy::TargetSnr(4.0),
+            ..CONFIG
+        };
+        let report = calibrate(&driver, &pads, &config).unwrap();
+        assert_eq!(report[0].threshold, 1020);
+        assert_eq!(report[0].status, CalibrationStatus::Ok);
+    }
+
+    #[test]
+    fn noisy_and_broken_pads_are_flagged() {
+        let sim = SimTouchHal::new();
+        let driver = driver(&sim);
+        let pads = [
+            driver.take_pad(TouchPadChannel::Num0).unwrap(),
+            driver.take_pad(TouchPadChannel::Num1).unwrap(),
+            driver.take_pad(TouchPadChannel::Num2).unwrap(),
+            driver.take_pad(TouchPadChannel::Num5).unwrap(),
+        ];
+        sim.set_noise(TouchPadChannel::Num1, 50);
+        sim.set_raw(TouchPadChannel::Num2, 0);
+
+        let report = calibrate(&driver, &pads, &CONFIG).unwrap();
+        let status: Vec<_> = report.iter().map(|c| (c.channel, c.status)).collect();
+        // Num5 is not enabled and is skipped.
+        assert_eq!(
+            status,
+            vec![
+                (TouchPadChannel::Num0, CalibrationStatus::Ok),
+                (TouchPadChannel::Num1, CalibrationStatus::HighNoise),
+                (TouchPadChannel::Num2, CalibrationStatus::BadConnection),
+            ]
+        );
+        assert_eq!(report[1].noise, 50.0);
+        assert_eq!(report[2].mean, 0.0);
+        // Only the pads calibrated `Ok` get their threshold.
+        assert_eq!(pads[0].get_thresh(), Ok(700));
+        assert_eq!(pads[1].get_thresh(), Ok(1));
+        assert_eq!(pads[2].get_thresh(), Ok(1));
+    }
+
+    #[test]
+    fn pads_of_another_driver_are_rejected() {
+        let sim = SimTouchHal::new();
+        let driver = driver(&sim);
+        let other = TouchDriver::new(SimTouchHal::new()).unwrap();
+        let pads = [
+            driver.take_pad(TouchPadChannel::Num0).unwrap(),
+            other.take_pad(TouchPadChannel::Num1).unwrap(),
+        ];
+
+        let err = calibrate(&driver, &pads, &CONFIG).unwrap_err();
+        assert_eq!(err.esp_err(), EspErr::EspErrInvalidArg);
+        assert_eq!(err.channel(), Some(TouchPadChannel::Num1));
+        assert_eq!(pads[0].get_thresh(), Ok(1));
+    }
+}
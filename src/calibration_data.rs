@@ -0,0 +1,407 @@
+use crate::baseline::BaselineTracker;
+use crate::calibrate::{CalibrationStatus, ChannelCalibration};
+use crate::driver::TouchDriver;
+use crate::hal::{CntMode, TouchHal, VoltageConfig};
+use crate::pad::TouchPad;
+use crate::storage::KvStorage;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Size of the blob header: version and payload length, both u16 little endian.
+const HEADER_LEN: usize = 4;
+/// Size of the CRC-32 trailing the blob.
+const CRC_LEN: usize = 4;
+/// Size of the voltage settings in the payload.
+const VOLTAGE_LEN: usize = 3;
+/// Size of one channel in a version 1 payload: flags, baseline and threshold. Version 1 is
+/// synthetic, see `CalibrationData`.
+const CHANNEL_LEN_V1: usize = 5;
+/// Size of one channel in a version 2 payload: version 1 plus noise, slope and tie option.
+const CHANNEL_LEN_V2: usize = 9;
+/// Flag set on the channels that hold calibration data.
+const FLAG_PRESENT: u8 = 0x01;
+
+/// Saved calibration of one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelData {
+    /// Untouched counter value.
+    pub baseline: u16,
+    pub threshold: u16,
+    /// Standard deviation of the untouched counter value, rounded.
+    pub noise: u16,
+    pub cnt_mode: CntMode,
+}
+
+/// Calibration data that survives a reboot, saved as a versioned blob with a CRC-32.
+///
+/// Blob layout, little endian: version (u16), payload length (u16), payload, CRC-32 of everything
+/// before it. The payload holds the high, low and attenuation voltages (one byte each), then for
+/// every channel a flags byte, the baseline, threshold and noise (u16) and the slope and tie
+/// option (one byte each). Blobs of an older version are migrated on load.
+///
+/// Version 2 is the first format that shipped. Version 1, without noise and charge/discharge
+/// speed, was never written by the crate: it is a synthetic format that keeps the migration path
+/// exercised until a real older version exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalibrationData {
+    pub voltage: VoltageConfig,
+    /// Calibration of every channel, indexed by `TouchPadChannel`. `None` for the channels that
+    /// were not calibrated.
+    pub channels: [Option<ChannelData>; TouchPadChannel::ALL.len()],
+}
+
+impl CalibrationData {
+    /// Version of the blobs written by `encode`.
+    pub const VERSION: u16 = 2;
+
+    pub fn new(voltage: VoltageConfig) -> Self {
+        CalibrationData {
+            voltage,
+            channels: [None; TouchPadChannel::ALL.len()],
+        }
+    }
+
+    /// Collect the channels of a `calibrate` report whose status is `Ok`, with the current
+    /// voltages and charge/discharge speed of the driver.
+    pub fn from_report<H: TouchHal>(
+        driver: &TouchDriver<H>,
+        report: &[ChannelCalibration],
+    ) -> Result<Self, TouchError> {
+        let mut data = CalibrationData::new(driver.get_voltage()?);
+        for calibration in report {
+            if calibration.status != CalibrationStatus::Ok {
+                continue;
+            }
+            data.set_channel(
+                calibration.channel,
+                ChannelData {
+                    baseline: to_u16(calibration.mean),
+                    threshold: calibration.threshold,
+                    noise: to_u16(calibration.noise),
+                    cnt_mode: driver.get_cnt_mode(calibration.channel)?,
+                },
+            );
+        }
+        Ok(data)
+    }
+
+    pub fn channel(&self, touch_num: TouchPadChannel) -> Option<&ChannelData> {
+        self.channels
+            .get(touch_num as usize)
+            .and_then(Option::as_ref)
+    }
+
+    /// Like `channel`, for the callers that need the channel to be calibrated.
+    ///
+    /// # Error
+    ///
+    /// * NotCalibrated the channel has no calibration data
+    pub fn calibrated(&self, touch_num: TouchPadChannel) -> Result<&ChannelData, TouchError> {
+        self.channel(touch_num)
+            .ok_or(TouchError::NotCalibrated(touch_num))
+    }
+
+    /// Set the calibration of a channel, ignored for `TouchPadChannel::Max`.
+    pub fn set_channel(&mut self, touch_num: TouchPadChannel, data: ChannelData) {
+        if let Some(channel) = self.channels.get_mut(touch_num as usize) {
+            *channel = Some(data);
+        }
+    }
+
+    /// Apply the voltages, then the charge/discharge speed and threshold of each pad that has
+    /// calibration data. The other pads are left untouched.
+    pub fn apply<H: TouchHal>(
+        &self,
+        driver: &TouchDriver<H>,
+        pads: &[TouchPad<'_, H>],
+    ) -> Result<(), TouchError> {
+        driver.set_voltage(
+            self.voltage.high,
+            self.voltage.low,
+            self.voltage.attenuation,
+        )?;
+        for pad in pads {
+            if let Some(data) = self.channel(pad.channel()) {
+                pad.set_cnt_mode(data.cnt_mode.slope, data.cnt_mode.opt)?;
+                pad.set_thresh(data.threshold)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Seed the baseline tracker with the saved baselines.
+    pub fn seed_baselines(&self, tracker: &mut BaselineTracker) {
+        for touch_num in TouchPadChannel::ALL {
+            if let Some(data) = self.channel(touch_num) {
+                tracker.set_baseline(touch_num, data.baseline);
+            }
+        }
+    }
+
+    /// Serialize to a blob of the current `VERSION`.
+    pub fn encode(&self) -> Vec<u8> {
+        let payload_len = VOLTAGE_LEN + CHANNEL_LEN_V2 * self.channels.len();
+        let mut blob = Vec::with_capacity(HEADER_LEN + payload_len + CRC_LEN);
+        blob.extend_from_slice(&Self::VERSION.to_le_bytes());
+        blob.extend_from_slice(&(payload_len as u16).to_le_bytes());
+        blob.push(self.voltage.high as i32 as u8);
+        blob.push(self.voltage.low as i32 as u8);
+        blob.push(self.voltage.attenuation as i32 as u8);
+        for channel in &self.channels {
+            let data = channel.unwrap_or(ChannelData {
+                baseline: 0,
+                threshold: 0,
+                noise: 0,
+                cnt_mode: DEFAULT_CNT_MODE,
+            });
+            blob.push(if channel.is_some() { FLAG_PRESENT } else { 0 });
+            blob.extend_from_slice(&data.baseline.to_le_bytes());
+            blob.extend_from_slice(&data.threshold.to_le_bytes());
+            blob.extend_from_slice(&data.noise.to_le_bytes());
+            blob.push(data.cnt_mode.slope as u32 as u8);
+            blob.push(data.cnt_mode.opt as u32 as u8);
+        }
+        let crc = crc32(&blob);
+        blob.extend_from_slice(&crc.to_le_bytes());
+        blob
+    }
+
+    /// Parse a blob written by `encode`, migrating older versions to the current one.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_SIZE the blob is truncated or its length does not match its version
+    /// * ESP_ERR_INVALID_CRC the CRC does not match, the blob is corrupted
+    /// * ESP_ERR_INVALID_VERSION the blob was written by an unknown version
+    /// * ESP_ERR_INVALID_RESPONSE the blob holds an out of range voltage or charge setting
+    pub fn decode(blob: &[u8]) -> Result<Self, EspErr> {
+        if blob.len() < HEADER_LEN + CRC_LEN {
+            return Err(EspErr::EspErrInvalidSize);
+        }
+        let (content, crc) = blob.split_at(blob.len() - CRC_LEN);
+        if crc32(content) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err(EspErr::EspErrInvalidCrc);
+        }
+        let version = u16::from_le_bytes([content[0], content[1]]);
+        let payload_len = u16::from_le_bytes([content[2], content[3]]) as usize;
+        let payload = &content[HEADER_LEN..];
+        let channel_len = match version {
+            1 => CHANNEL_LEN_V1,
+            Self::VERSION => CHANNEL_LEN_V2,
+            _ => return Err(EspErr::EspErrInvalidVersion),
+        };
+        if payload.len() != payload_len
+            || payload_len != VOLTAGE_LEN + channel_len * TouchPadChannel::ALL.len()
+        {
+            return Err(EspErr::EspErrInvalidSize);
+        }
+
+        let voltage = VoltageConfig {
+            high: checked(TouchHighVoltage::from_raw(payload[0] as i8 as i32))?,
+            low: checked(TouchLowVoltage::from_raw(payload[1] as i8 as i32))?,
+            attenuation: checked(TouchVoltageAttenuation::from_raw(payload[2] as i8 as i32))?,
+        };
+        let mut data = CalibrationData::new(voltage);
+        for (channel, bytes) in data
+            .channels
+            .iter_mut()
+            .zip(payload[VOLTAGE_LEN..].chunks_exact(channel_len))
+        {
+            if bytes[0] & FLAG_PRESENT == 0 {
+                continue;
+            }
+            let baseline = u16::from_le_bytes([bytes[1], bytes[2]]);
+            let threshold = u16::from_le_bytes([bytes[3], bytes[4]]);
+            *channel = Some(if version == 1 {
+                // Version 1 did not save the noise and charge/discharge speed.
+                ChannelData {
+                    baseline,
+                    threshold,
+                    noise: 0,
+                    cnt_mode: DEFAULT_CNT_MODE,
+                }
+            } else {
+                ChannelData {
+                    baseline,
+                    threshold,
+                    noise: u16::from_le_bytes([bytes[5], bytes[6]]),
+                    cnt_mode: CntMode {
+                        slope: checked(TouchCountSlope::from_raw(bytes[7] as u32))?,
+                        opt: checked(TouchTieOption::from_raw(bytes[8] as u32))?,
+                    },
+                }
+            });
+        }
+        Ok(data)
+    }
+
+    /// Save the blob under `key`.
+    pub fn save<S: KvStorage + ?Sized>(&self, storage: &mut S, key: &str) -> Result<(), EspErr> {
+        storage.set_blob(key, &self.encode())
+    }
+
+    /// Load the blob saved under `key`, `None` if there is none. A blob of an older version is
+    /// migrated and saved again in the current version.
+    ///
+    /// # Errors
+    ///
+    /// * Those of `decode` and of the storage
+    pub fn load<S: KvStorage + ?Sized>(storage: &mut S, key: &str) -> Result<Option<Self>, EspErr> {
+        let Some(blob) = storage.get_blob(key)? else {
+            return Ok(None);
+        };
+        let data = Self::decode(&blob)?;
+        if blob_version(&blob) != Self::VERSION {
+            data.save(storage, key)?;
+        }
+        Ok(Some(data))
+    }
+}
+
+/// Charge/discharge speed of the IDF after `touch_pad_init`, used for the channels of version 1
+/// blobs.
+const DEFAULT_CNT_MODE: CntMode = CntMode {
+    slope: TouchCountSlope::Slope7,
+    opt: TouchTieOption::Low,
+};
+
+fn to_u16(value: f32) -> u16 {
+    value.round().clamp(0.0, u16::MAX as f32) as u16
+}
+
+fn blob_version(blob: &[u8]) -> u16 {
+    u16::from_le_bytes([blob[0], blob[1]])
+}
+
+/// CRC-32 (IEEE 802.3, as used by zlib).
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn sample() -> CalibrationData {
+        let mut data = CalibrationData::new(VoltageConfig {
+            high: TouchHighVoltage::V2_5,
+            low: TouchLowVoltage::V0_6,
+            attenuation: TouchVoltageAttenuation::V1_0,
+        });
+        data.set_channel(
+            TouchPadChannel::Num3,
+            ChannelData {
+                baseline: 1200,
+                threshold: 800,
+                noise: 6,
+                cnt_mode: CntMode {
+                    slope: TouchCountSlope::Slope5,
+                    opt: TouchTieOption::High,
+                },
+            },
+        );
+        data
+    }
+
+    /// Blob with a valid header and CRC around `payload`.
+    fn blob(version: u16, payload: &[u8]) -> Vec<u8> {
+        let mut blob = version.to_le_bytes().to_vec();
+        blob.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        blob.extend_from_slice(payload);
+        let crc = crc32(&blob);
+        blob.extend_from_slice(&crc.to_le_bytes());
+        blob
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = MemoryStorage::new();
+        assert_eq!(CalibrationData::load(&mut storage, "touch"), Ok(None));
+        sample().save(&mut storage, "touch").unwrap();
+        assert_eq!(
+            CalibrationData::load(&mut storage, "touch"),
+            Ok(Some(sample()))
+        );
+        assert_eq!(
+            blob_version(&storage.get_blob("touch").unwrap().unwrap()),
+            2
+        );
+    }
+
+    #[test]
+    fn corrupted_blobs_are_rejected() {
+        let encoded = sample().encode();
+        for index in [0, 5, encoded.len() - 1] {
+            let mut flipped = encoded.clone();
+            flipped[index] ^= 0x10;
+            assert_eq!(
+                CalibrationData::decode(&flipped),
+                Err(EspErr::EspErrInvalidCrc)
+            );
+        }
+
+        assert_eq!(
+            CalibrationData::decode(&encoded[..HEADER_LEN]),
+            Err(EspErr::EspErrInvalidSize)
+        );
+        let payload = &encoded[HEADER_LEN..encoded.len() - CRC_LEN];
+        assert_eq!(
+            CalibrationData::decode(&blob(2, &payload[..payload.len() - 1])),
+            Err(EspErr::EspErrInvalidSize)
+        );
+        assert_eq!(
+            CalibrationData::decode(&blob(3, payload)),
+            Err(EspErr::EspErrInvalidVersion)
+        );
+        assert_eq!(
+            CalibrationData::decode(&blob(0, payload)),
+            Err(EspErr::EspErrInvalidVersion)
+        );
+    }
+
+    #[test]
+    fn version_1_is_migrated() {
+        let mut payload = vec![
+            TouchHighVoltage::V2_5 as i32 as u8,
+            TouchLowVoltage::V0_6 as i32 as u8,
+            TouchVoltageAttenuation::V1_0 as i32 as u8,
+        ];
+        for touch_num in TouchPadChannel::ALL {
+            if touch_num == TouchPadChannel::Num3 {
+                payload.push(FLAG_PRESENT);
+                payload.extend_from_slice(&1200u16.to_le_bytes());
+                payload.extend_from_slice(&800u16.to_le_bytes());
+            } else {
+                payload.extend_from_slice(&[0; CHANNEL_LEN_V1]);
+            }
+        }
+        let mut storage = MemoryStorage::new();
+        storage.set_blob("touch", &blob(1, &payload)).unwrap();
+
+        let mut expected = sample();
+        expected.set_channel(
+            TouchPadChannel::Num3,
+            ChannelData {
+                baseline: 1200,
+                threshold: 800,
+                noise: 0,
+                cnt_mode: DEFAULT_CNT_MODE,
+            },
+        );
+        assert_eq!(
+            CalibrationData::load(&mut storage, "touch"),
+            Ok(Some(expected))
+        );
+        let saved = storage.get_blob("touch").unwrap().unwrap();
+        assert_eq!(saved, expected.encode());
+        assert_eq!(blob_version(&saved), CalibrationData::VERSION);
+    }
+}
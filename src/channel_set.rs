@@ -0,0 +1,335 @@
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not, Sub, SubAssign};
+
+/// Set of touch channels, stored as a mask with one bit per channel, as in the status and group
+/// masks of the peripheral. Only the ten channel bits can be set, so every set is a valid mask.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ChannelSet(u16);
+
+impl ChannelSet {
+    /// Mask of the bits that map to a channel.
+    const VALID_BITS: u16 = (1 << TouchPadChannel::ALL.len()) - 1;
+
+    /// Every touch channel.
+    pub const ALL: ChannelSet = ChannelSet(Self::VALID_BITS);
+
+    pub const fn new() -> Self {
+        ChannelSet(0)
+    }
+
+    /// Set from a mask, bit `n` being `TouchPadChannel` number `n`.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG a bit above channel 9 is set
+    pub const fn from_bits(bits: u16) -> Result<Self, EspErr> {
+        if bits & !Self::VALID_BITS != 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        Ok(ChannelSet(bits))
+    }
+
+    /// Set from a mask read from the peripheral, bits that are not a channel are dropped.
+    pub(crate) fn from_bits_truncate(bits: u32) -> Self {
+        ChannelSet(bits as u16 & Self::VALID_BITS)
+    }
+
+    /// The mask, bit `n` being `TouchPadChannel` number `n`.
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn contains(&self, touch_num: TouchPadChannel) -> bool {
+        self.0 & Self::bit(touch_num) != 0
+    }
+
+    pub fn insert(&mut self, touch_num: TouchPadChannel) {
+        self.0 |= Self::bit(touch_num);
+    }
+
+    pub fn remove(&mut self, touch_num: TouchPadChannel) {
+        self.0 &= !Self::bit(touch_num);
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn union(self, other: ChannelSet) -> ChannelSet {
+        ChannelSet(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: ChannelSet) -> ChannelSet {
+        ChannelSet(self.0 & other.0)
+    }
+
+    /// Channels of `self` that are not in `other`.
+    pub const fn difference(self, other: ChannelSet) -> ChannelSet {
+        ChannelSet(self.0 & !other.0)
+    }
+
+    /// Channels that are not in `self`.
+    pub const fn complement(self) -> ChannelSet {
+        ChannelSet(!self.0 & Self::VALID_BITS)
+    }
+
+    pub const fn is_subset(&self, other: &ChannelSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// Channels of the set, in channel order.
+    pub fn iter(&self) -> Iter {
+        Iter(self.0)
+    }
+
+    /// Bit of a channel, 0 for `TouchPadChannel::Max`.
+    fn bit(touch_num: TouchPadChannel) -> u16 {
+        (1u32 << touch_num as u32) as u16 & Self::VALID_BITS
+    }
+}
+
+/// Set of one channel, empty for `TouchPadChannel::Max`.
+impl From<TouchPadChannel> for ChannelSet {
+    fn from(touch_num: TouchPadChannel) -> Self {
+        ChannelSet(Self::bit(touch_num))
+    }
+}
+
+impl TryFrom<u16> for ChannelSet {
+    type Error = EspErr;
+
+    fn try_from(bits: u16) -> Result<Self, EspErr> {
+        ChannelSet::from_bits(bits)
+    }
+}
+
+impl From<ChannelSet> for u16 {
+    fn from(set: ChannelSet) -> Self {
+        set.0
+    }
+}
+
+impl FromIterator<TouchPadChannel> for ChannelSet {
+    fn from_iter<I: IntoIterator<Item = TouchPadChannel>>(iter: I) -> Self {
+        let mut set = ChannelSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<TouchPadChannel> for ChannelSet {
+    fn extend<I: IntoIterator<Item = TouchPadChannel>>(&mut self, iter: I) {
+        for touch_num in iter {
+            self.insert(touch_num);
+        }
+    }
+}
+
+impl IntoIterator for ChannelSet {
+    type Item = TouchPadChannel;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        Iter(self.0)
+    }
+}
+
+/// Iterator over the channels of a `ChannelSet`, in channel order.
+#[derive(Debug, Clone)]
+pub struct Iter(u16);
+
+impl Iterator for Iter {
+    type Item = TouchPadChannel;
+
+    fn next(&mut self) -> Option<TouchPadChannel> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        TouchPadChannel::ALL.get(index).copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+impl BitOr for ChannelSet {
+    type Output = ChannelSet;
+
+    fn bitor(self, other: ChannelSet) -> ChannelSet {
+        self.union(other)
+    }
+}
+
+impl BitOrAssign for ChannelSet {
+    fn bitor_assign(&mut self, other: ChannelSet) {
+        *self = self.union(other);
+    }
+}
+
+impl BitAnd for ChannelSet {
+    type Output = ChannelSet;
+
+    fn bitand(self, other: ChannelSet) -> ChannelSet {
+        self.intersection(other)
+    }
+}
+
+impl BitAndAssign for ChannelSet {
+    fn bitand_assign(&mut self, other: ChannelSet) {
+        *self = self.intersection(other);
+    }
+}
+
+impl Sub for ChannelSet {
+    type Output = ChannelSet;
+
+    fn sub(self, other: ChannelSet) -> ChannelSet {
+        self.difference(other)
+    }
+}
+
+impl SubAssign for ChannelSet {
+    fn sub_assign(&mut self, other: ChannelSet) {
+        *self = self.difference(other);
+    }
+}
+
+impl Not for ChannelSet {
+    type Output = ChannelSet;
+
+    fn not(self) -> ChannelSet {
+        self.complement()
+    }
+}
+
+/// Channel numbers in braces, e.g. `{0, 3, 7}`.
+impl fmt::Display for ChannelSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("{")?;
+        for (index, touch_num) in self.iter().enumerate() {
+            if index != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", touch_num as u32)?;
+        }
+        f.write_str("}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::TouchDriver;
+    use crate::hal::GroupMask;
+    use crate::sim::SimTouchHal;
+
+    #[test]
+    fn insert_remove_and_contains() {
+        let mut set = ChannelSet::new();
+        assert!(set.is_empty());
+        set.insert(TouchPadChannel::Num0);
+        set.insert(TouchPadChannel::Num9);
+        set.insert(TouchPadChannel::Num9);
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(TouchPadChannel::Num0));
+        assert!(set.contains(TouchPadChannel::Num9));
+        assert!(!set.contains(TouchPadChannel::Num5));
+
+        set.remove(TouchPadChannel::Num0);
+        set.remove(TouchPadChannel::Num5);
+        assert_eq!(set, ChannelSet::from(TouchPadChannel::Num9));
+
+        // `Max` has no bit.
+        set.insert(TouchPadChannel::Max);
+        assert!(!set.contains(TouchPadChannel::Max));
+        assert_eq!(set.bits(), 1 << 9);
+        assert!(ChannelSet::from(TouchPadChannel::Max).is_empty());
+    }
+
+    #[test]
+    fn iteration_is_in_channel_order() {
+        let set: ChannelSet = [
+            TouchPadChannel::Num7,
+            TouchPadChannel::Num0,
+            TouchPadChannel::Num3,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.bits(), 0b1000_1001);
+        assert_eq!(set.iter().len(), 3);
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![
+                TouchPadChannel::Num0,
+                TouchPadChannel::Num3,
+                TouchPadChannel::Num7,
+            ]
+        );
+        assert_eq!(set.to_string(), "{0, 3, 7}");
+        assert_eq!(ChannelSet::new().to_string(), "{}");
+        assert!(ChannelSet::ALL.iter().eq(TouchPadChannel::ALL));
+    }
+
+    #[test]
+    fn set_operations() {
+        let low = ChannelSet::from_bits(0b0000_0111).unwrap();
+        let odd = ChannelSet::from_bits(0b10_1010_1010).unwrap();
+        assert_eq!((low | odd).bits(), 0b10_1010_1111);
+        assert_eq!((low & odd).bits(), 0b10);
+        assert_eq!((low - odd).bits(), 0b101);
+        assert_eq!((!low).bits(), 0b11_1111_1000);
+        assert!((low & odd).is_subset(&low));
+        assert!(!low.is_subset(&odd));
+        assert_eq!(!ChannelSet::ALL, ChannelSet::new());
+    }
+
+    #[test]
+    fn bits_outside_the_channels_are_rejected() {
+        assert_eq!(ChannelSet::from_bits(0x3ff), Ok(ChannelSet::ALL));
+        for bit in 10..16 {
+            assert_eq!(
+                ChannelSet::from_bits(1 << bit),
+                Err(EspErr::EspErrInvalidArg)
+            );
+        }
+        assert_eq!(ChannelSet::try_from(0x400), Err(EspErr::EspErrInvalidArg));
+        assert_eq!(ChannelSet::from_bits_truncate(0xffff_fc01).bits(), 1);
+        assert_eq!(u16::from(ChannelSet::ALL), 0x3ff);
+    }
+
+    #[test]
+    fn group_mask_round_trips() {
+        let driver = TouchDriver::new(SimTouchHal::new()).unwrap();
+        let set1 = ChannelSet::from_bits(0b0011).unwrap();
+        let set2 = ChannelSet::from_bits(0b0110).unwrap();
+        let en = ChannelSet::from_bits(0b10_0000_0111).unwrap();
+        driver.set_group_mask(set1, set2, en).unwrap();
+        assert_eq!(
+            driver.get_group_mask(),
+            Ok(GroupMask {
+                set1_mask: set1,
+                set2_mask: set2,
+                en_mask: en,
+            })
+        );
+
+        let first = ChannelSet::from(TouchPadChannel::Num0);
+        driver.clear_group_mask(first, first, first).unwrap();
+        let group_mask = driver.get_group_mask().unwrap();
+        assert_eq!(group_mask.set1_mask.bits(), 0b0010);
+        assert_eq!(group_mask.set2_mask, set2);
+        assert_eq!(group_mask.en_mask.bits(), 0b10_0000_0110);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::touch_pad_enum::*;
+
+#[cfg(any(
+    all(feature = "esp32", feature = "esp32s2"),
+    all(feature = "esp32", feature = "esp32s3"),
+    all(feature = "esp32s2", feature = "esp32s3"),
+))]
+compile_error!("only one of the `esp32`, `esp32s2` and `esp32s3` features can be enabled");
+
+/// Chip variant, which decides the GPIO wired to each touch channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chip {
+    /// Channel 0 to 9 on GPIO4, 0, 2, 15, 13, 12, 14, 27, 33 and 32.
+    Esp32,
+    /// Channel n on GPIOn. Channel 0 is internal and has no GPIO. Channels 10 to 14 have no
+    /// `TouchPadChannel`, so the driver cannot use them.
+    Esp32S2,
+    /// Channel n on GPIOn. Channel 0 is internal and has no GPIO. Channels 10 to 14 have no
+    /// `TouchPadChannel`, so the driver cannot use them.
+    Esp32S3,
+}
+
+impl Chip {
+    /// Chip selected by the `esp32`, `esp32s2` or `esp32s3` feature, ESP32 when none is enabled.
+    #[cfg(not(any(feature = "esp32s2", feature = "esp32s3")))]
+    pub const CURRENT: Chip = Chip::Esp32;
+    /// Chip selected by the `esp32`, `esp32s2` or `esp32s3` feature, ESP32 when none is enabled.
+    #[cfg(feature = "esp32s2")]
+    pub const CURRENT: Chip = Chip::Esp32S2;
+    /// Chip selected by the `esp32`, `esp32s2` or `esp32s3` feature, ESP32 when none is enabled.
+    #[cfg(feature = "esp32s3")]
+    pub const CURRENT: Chip = Chip::Esp32S3;
+}
+
+impl TouchPadChannel {
+    /// GPIO of the channel on a chip, `GpioNum::GpioNumNc` if the channel has no pad there.
+    pub const fn gpio(self, chip: Chip) -> GpioNum {
+        match chip {
+            Chip::Esp32 => match self {
+                TouchPadChannel::Num0 => GpioNum::GpioNum4,
+                TouchPadChannel::Num1 => GpioNum::GpioNum0,
+                TouchPadChannel::Num2 => GpioNum::GpioNum2,
+                TouchPadChannel::Num3 => GpioNum::GpioNum15,
+                TouchPadChannel::Num4 => GpioNum::GpioNum13,
+                TouchPadChannel::Num5 => GpioNum::GpioNum12,
+                TouchPadChannel::Num6 => GpioNum::GpioNum14,
+                TouchPadChannel::Num7 => GpioNum::GpioNum27,
+                TouchPadChannel::Num8 => GpioNum::GpioNum33,
+                TouchPadChannel::Num9 => GpioNum::GpioNum32,
+                TouchPadChannel::Max => GpioNum::GpioNumNc,
+            },
+            Chip::Esp32S2 | Chip::Esp32S3 => match self {
+                TouchPadChannel::Num1 => GpioNum::GpioNum1,
+                TouchPadChannel::Num2 => GpioNum::GpioNum2,
+                TouchPadChannel::Num3 => GpioNum::GpioNum3,
+                TouchPadChannel::Num4 => GpioNum::GpioNum4,
+                TouchPadChannel::Num5 => GpioNum::GpioNum5,
+                TouchPadChannel::Num6 => GpioNum::GpioNum6,
+                TouchPadChannel::Num7 => GpioNum::GpioNum7,
+                TouchPadChannel::Num8 => GpioNum::GpioNum8,
+                TouchPadChannel::Num9 => GpioNum::GpioNum9,
+                TouchPadChannel::Num0 | TouchPadChannel::Max => GpioNum::GpioNumNc,
+            },
+        }
+    }
+
+    /// Channel wired to a GPIO on a chip, `None` if the GPIO is not a touch pad there. Only
+    /// channels 0 to 9 are representable, so GPIO10 to GPIO14 of the ESP32-S2 and ESP32-S3
+    /// give `None` too, although they are touch pads.
+    pub fn from_gpio(chip: Chip, gpio: GpioNum) -> Option<TouchPadChannel> {
+        if gpio == GpioNum::GpioNumNc {
+            return None;
+        }
+        TouchPadChannel::ALL
+            .into_iter()
+            .find(|channel| channel.gpio(chip) == gpio)
+    }
+
+    /// Whether the channel has a pad on a chip.
+    pub const fn is_available(self, chip: Chip) -> bool {
+        !matches!(self.gpio(chip), GpioNum::GpioNumNc)
+    }
+
+    /// The channel itself, panicking if it has no pad on the chip. Evaluated in a constant, as
+    /// done by `touch_channel!`, the panic is a compile-time error.
+    pub const fn require(self, chip: Chip) -> Self {
+        if !self.is_available(chip) {
+            panic!("touch channel not available on the selected chip");
+        }
+        self
+    }
+}
+
+impl GpioNum {
+    /// Whether the GPIO exists on a chip. GPIO20 of the ESP32 is only bonded out on the
+    /// ESP32-PICO-V3 and is left out.
+    pub const fn exists(self, chip: Chip) -> bool {
+        let num = self as i32;
+        match chip {
+            Chip::Esp32 => matches!(num, 0..=19 | 21..=23 | 25..=27 | 32..=39),
+            Chip::Esp32S2 => matches!(num, 0..=21 | 26..=46),
+            Chip::Esp32S3 => matches!(num, 0..=21 | 26..=48),
+        }
+    }
+}
+
+/// `TouchPadChannel` checked at compile time against `Chip::CURRENT`, e.g.
+/// `touch_channel!(Num3)`. A channel without a pad on the selected chip does not compile.
+#[macro_export]
+macro_rules! touch_channel {
+    ($channel:ident) => {{
+        const CHANNEL: $crate::touch_pad_enum::TouchPadChannel =
+            $crate::touch_pad_enum::TouchPadChannel::$channel.require($crate::chip::Chip::CURRENT);
+        CHANNEL
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpio_round_trips() {
+        for chip in [Chip::Esp32, Chip::Esp32S2, Chip::Esp32S3] {
+            for channel in TouchPadChannel::ALL {
+                let gpio = channel.gpio(chip);
+                if channel.is_available(chip) {
+                    assert!(gpio.exists(chip));
+                    assert_eq!(TouchPadChannel::from_gpio(chip, gpio), Some(channel));
+                }
+            }
+        }
+        assert_eq!(
+            TouchPadChannel::Num0.gpio(Chip::Esp32S2),
+            GpioNum::GpioNumNc
+        );
+        assert_eq!(
+            TouchPadChannel::from_gpio(Chip::Esp32S3, GpioNum::GpioNum12),
+            None
+        );
+        assert_eq!(
+            TouchPadChannel::from_gpio(Chip::Esp32, GpioNum::GpioNum12),
+            Some(TouchPadChannel::Num5)
+        );
+    }
+
+    #[test]
+    fn gpios_per_chip() {
+        assert!(!GpioNum::GpioNum24.exists(Chip::Esp32));
+        assert!(!GpioNum::GpioNum28.exists(Chip::Esp32));
+        assert!(GpioNum::GpioNum28.exists(Chip::Esp32S2));
+        assert!(GpioNum::GpioNum39.exists(Chip::Esp32));
+        assert!(!GpioNum::GpioNum47.exists(Chip::Esp32S2));
+        assert!(GpioNum::GpioNum48.exists(Chip::Esp32S3));
+        assert!(!GpioNum::GpioNumNc.exists(Chip::Esp32S3));
+        assert!(!GpioNum::GpioNumMax.exists(Chip::Esp32S3));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::channel_set::ChannelSet;
+use crate::driver::{TouchDriver, TouchFilter};
+use crate::hal::{CntMode, GroupMask, MeasTime, TouchHal, VoltageConfig};
+use crate::pad::TouchPad;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Configuration of one pad in a `TouchConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PadConfig {
+    pub channel: TouchPadChannel,
+    /// Interrupt threshold, must not be 0.
+    pub threshold: u16,
+    pub cnt_mode: CntMode,
+}
+
+impl PadConfig {
+    /// Pad with the default charge/discharge speed of the IDF.
+    pub fn new(channel: TouchPadChannel, threshold: u16) -> Self {
+        PadConfig {
+            channel,
+            threshold,
+            cnt_mode: CntMode {
+                slope: TouchCountSlope::Slope7,
+                opt: TouchTieOption::Low,
+            },
+        }
+    }
+}
+
+/// Whole touch sensor setup, applied in one go by `apply`. Build it with `TouchConfig::builder`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TouchConfig {
+    pub fsm_mode: TouchFSMMode,
+    pub voltage: VoltageConfig,
+    /// Sleep cycles between measurements (the measurement interval) and clock cycles of each
+    /// measurement.
+    pub meas_time: MeasTime,
+    pub pads: Vec<PadConfig>,
+    pub trigger_mode: TouchTriggerMode,
+    pub trigger_source: TouchTriggerSource,
+    /// Group masks, `None` to put every pad in set 1 and enable it.
+    pub group_mask: Option<GroupMask>,
+    /// Start the IIR filter with this period, in ms.
+    pub filter_period_ms: Option<u32>,
+    pub intr_enable: bool,
+}
+
+impl Default for TouchConfig {
+    fn default() -> Self {
+        TouchConfig {
+            fsm_mode: TouchFSMMode::Timer,
+            voltage: VoltageConfig {
+                high: TouchHighVoltage::V2_7,
+                low: TouchLowVoltage::V0_5,
+                attenuation: TouchVoltageAttenuation::V1_0,
+            },
+            meas_time: MeasTime {
+                sleep_cycle: 0x1000,
+                meas_cycle: 0x7fff,
+            },
+            pads: Vec::new(),
+            trigger_mode: TouchTriggerMode::Below,
+            trigger_source: TouchTriggerSource::Set1,
+            group_mask: None,
+            filter_period_ms: None,
+            intr_enable: false,
+        }
+    }
+}
+
+/// Setting read back with a different value than the one applied, or that differs between two
+/// `TouchSettings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigMismatch {
+    FsmMode {
+        expected: TouchFSMMode,
+        actual: TouchFSMMode,
+    },
+    Voltage {
+        expected: VoltageConfig,
+        actual: VoltageConfig,
+    },
+    MeasTime {
+        expected: MeasTime,
+        actual: MeasTime,
+    },
+    CntMode {
+        channel: TouchPadChannel,
+        expected: CntMode,
+        actual: CntMode,
+    },
+    Threshold {
+        channel: TouchPadChannel,
+        expected: u16,
+        actual: u16,
+    },
+    TriggerMode {
+        expected: TouchTriggerMode,
+        actual: TouchTriggerMode,
+    },
+    TriggerSource {
+        expected: TouchTriggerSource,
+        actual: TouchTriggerSource,
+    },
+    GroupMask {
+        expected: GroupMask,
+        actual: GroupMask,
+    },
+    /// `None` when the filter is not running.
+    FilterPeriod {
+        expected: Option<u32>,
+        actual: Option<u32>,
+    },
+}
+
+/// Result of `TouchConfig::apply`.
+pub struct AppliedConfig<'d, H: TouchHal> {
+    /// Running filter when `filter_period_ms` is set. Dropping it stops the filter.
+    pub filter: Option<TouchFilter<'d, H>>,
+    /// Settings the hardware did not take, empty when everything was applied.
+    pub mismatches: Vec<ConfigMismatch>,
+}
+
+impl TouchConfig {
+    pub fn builder() -> TouchConfigBuilder {
+        TouchConfigBuilder {
+            config: TouchConfig::default(),
+        }
+    }
+
+    /// Group masks applied by `apply`.
+    pub fn effective_group_mask(&self) -> GroupMask {
+        self.group_mask.unwrap_or_else(|| {
+            let pads: ChannelSet = self.pads.iter().map(|pad| pad.channel).collect();
+            GroupMask {
+                set1_mask: pads,
+                set2_mask: ChannelSet::new(),
+                en_mask: pads,
+            }
+        })
+    }
+
+    /// Check the configuration without touching the hardware.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG a `Max` or `Keep` value, a pad listed twice, a threshold or
+    ///   measurement time of 0
+    pub fn validate(&self) -> Result<(), EspErr> {
+        let VoltageConfig {
+            high,
+            low,
+            attenuation,
+        } = self.voltage;
+        let invalid = self.fsm_mode == TouchFSMMode::Max
+            || matches!(high, TouchHighVoltage::Keep | TouchHighVoltage::Max)
+            || matches!(low, TouchLowVoltage::Keep | TouchLowVoltage::Max)
+            || matches!(
+                attenuation,
+                TouchVoltageAttenuation::Keep | TouchVoltageAttenuation::Max
+            )
+            || self.meas_time.meas_cycle == 0
+            || self.trigger_mode == TouchTriggerMode::Max
+            || self.trigger_source == TouchTriggerSource::Max;
+        if invalid {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        let mut seen = ChannelSet::new();
+        for pad in &self.pads {
+            if pad.channel == TouchPadChannel::Max
+                || seen.contains(pad.channel)
+                || pad.threshold == 0
+                || pad.cnt_mode.slope == TouchCountSlope::Max
+                || pad.cnt_mode.opt == TouchTieOption::Max
+            {
+                return Err(EspErr::EspErrInvalidArg);
+            }
+            seen.insert(pad.channel);
+        }
+        Ok(())
+    }
+
+    /// Apply the configuration in the order the IDF expects, then read every setting back and
+    /// report the ones that differ. `pads` must hold the handle of every configured pad.
+    ///
+    /// # Errors
+    ///
+    /// * `TouchError::InvalidConfig` the configuration is not valid, see `validate`
+    /// * `TouchError::PadMissing` a configured pad is missing from `pads`
+    /// * ESP_ERR_INVALID_STATE the filter is already running
+    pub fn apply<'d, H: TouchHal>(
+        &self,
+        driver: &'d TouchDriver<H>,
+        pads: &[TouchPad<'d, H>],
+    ) -> Result<AppliedConfig<'d, H>, TouchError> {
+        self.validate().map_err(|_| TouchError::InvalidConfig)?;
+        let pad_handles = self
+            .pads
+            .iter()
+            .map(|config| {
+                pads.iter()
+                    .find(|pad| pad.channel() == config.channel)
+                    .ok_or(TouchError::PadMissing(config.channel))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        driver.set_fsm_mode(self.fsm_mode)?;
+        driver.set_voltage(
+            self.voltage.high,
+            self.voltage.low,
+            self.voltage.attenuation,
+        )?;
+        driver.set_meas_time(self.meas_time.sleep_cycle, self.meas_time.meas_cycle)?;
+        for (config, pad) in self.pads.iter().zip(&pad_handles) {
+            // `configure` resets the charge/discharge speed, so it has to come first.
+            pad.configure(config.threshold)?;
+            pad.set_cnt_mode(config.cnt_mode.slope, config.cnt_mode.opt)?;
+        }
+        driver.set_trigger_mode(self.trigger_mode)?;
+        driver.set_trigger_source(self.trigger_source)?;
+        let group_mask = self.effective_group_mask();
+        driver.clear_group_mask(ChannelSet::ALL, ChannelSet::ALL, ChannelSet::ALL)?;
+        driver.set_group_mask(
+            group_mask.set1_mask,
+            group_mask.set2_mask,
+            group_mask.en_mask,
+        )?;
+        let filter = self
+            .filter_period_ms
+            .map(|period_ms| driver.filter_start(period_ms))
+            .transpose()?;
+        if self.intr_enable {
+            driver.intr_enable()?;
+        }
+
+        let mut mismatches = Vec::new();
+        let mut check = |mismatch: Option<ConfigMismatch>| mismatches.extend(mismatch);
+        let fsm_mode = driver.get_fsm_mode()?;
+        check(
+            (fsm_mode != self.fsm_mode).then_some(ConfigMismatch::FsmMode {
+                expected: self.fsm_mode,
+                actual: fsm_mode,
+            }),
+        );
+        let voltage = driver.get_voltage()?;
+        check(
+            (voltage != self.voltage).then_some(ConfigMismatch::Voltage {
+                expected: self.voltage,
+                actual: voltage,
+            }),
+        );
+        let meas_time = driver.get_meas_time()?;
+        check(
+            (meas_time != self.meas_time).then_some(ConfigMismatch::MeasTime {
+                expected: self.meas_time,
+                actual: meas_time,
+            }),
+        );
+        for (config, pad) in self.pads.iter().zip(&pad_handles) {
+            let cnt_mode = pad.get_cnt_mode()?;
+            check(
+                (cnt_mode != config.cnt_mode).then_some(ConfigMismatch::CntMode {
+                    channel: config.channel,
+                    expected: config.cnt_mode,
+                    actual: cnt_mode,
+                }),
+            );
+            let threshold = pad.get_thresh()?;
+            check(
+                (threshold != config.threshold).then_some(ConfigMismatch::Threshold {
+                    channel: config.channel,
+                    expected: config.threshold,
+                    actual: threshold,
+                }),
+            );
+        }
+        let trigger_mode = driver.get_trigger_mode()?;
+        check(
+            (trigger_mode != self.trigger_mode).then_some(ConfigMismatch::TriggerMode {
+                expected: self.trigger_mode,
+                actual: trigger_mode,
+            }),
+        );
+        let trigger_source = driver.get_trigger_source()?;
+        check(
+            (trigger_source != self.trigger_source).then_some(ConfigMismatch::TriggerSource {
+                expected: self.trigger_source,
+                actual: trigger_source,
+            }),
+        );
+        let actual_group_mask = driver.get_group_mask()?;
+        check(
+            (actual_group_mask != group_mask).then_some(ConfigMismatch::GroupMask {
+                expected: group_mask,
+                actual: actual_group_mask,
+            }),
+        );
+        if let Some(filter) = &filter {
+            let actual = Some(filter.get_filter_period()?);
+            check(
+                (actual != self.filter_period_ms).then_some(ConfigMismatch::FilterPeriod {
+                    expected: self.filter_period_ms,
+                    actual,
+                }),
+            );
+        }
+
+        Ok(AppliedConfig { filter, mismatches })
+    }
+}
+
+/// Builder of a `TouchConfig`, starting from the defaults.
+#[derive(Debug, Clone)]
+pub struct TouchConfigBuilder {
+    config: TouchConfig,
+}
+
+impl TouchConfigBuilder {
+    pub fn fsm_mode(mut self, mode: TouchFSMMode) -> Self {
+        self.config.fsm_mode = mode;
+        self
+    }
+
+    pub fn voltage(
+        mut self,
+        high: TouchHighVoltage,
+        low: TouchLowVoltage,
+        attenuation: TouchVoltageAttenuation,
+    ) -> Self {
+        self.config.voltage = VoltageConfig {
+            high,
+            low,
+            attenuation,
+        };
+        self
+    }
+
+    pub fn meas_time(mut self, sleep_cycle: u16, meas_cycle: u16) -> Self {
+        self.config.meas_time = MeasTime {
+            sleep_cycle,
+            meas_cycle,
+        };
+        self
+    }
+
+    /// Add a pad with the default charge/discharge speed.
+    pub fn pad(self, channel: TouchPadChannel, threshold: u16) -> Self {
+        self.pad_config(PadConfig::new(channel, threshold))
+    }
+
+    pub fn pad_config(mut self, pad: PadConfig) -> Self {
+        self.config.pads.push(pad);
+        self
+    }
+
+    pub fn trigger_mode(mut self, mode: TouchTriggerMode) -> Self {
+        self.config.trigger_mode = mode;
+        self
+    }
+
+    pub fn trigger_source(mut self, src: TouchTriggerSource) -> Self {
+        self.config.trigger_source = src;
+        self
+    }
+
+    pub fn group_mask(
+        mut self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Self {
+        self.config.group_mask = Some(GroupMask {
+            set1_mask,
+            set2_mask,
+            en_mask,
+        });
+        self
+    }
+
+    pub fn filter(mut self, period_ms: u32) -> Self {
+        self.config.filter_period_ms = Some(period_ms);
+        self
+    }
+
+    pub fn intr_enable(mut self, enable: bool) -> Self {
+        self.config.intr_enable = enable;
+        self
+    }
+
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG see `TouchConfig::validate`
+    pub fn build(self) -> Result<TouchConfig, EspErr> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+
+    #[test]
+    fn apply_keeps_cnt_mode() {
+        let driver = TouchDriver::new(SimTouchHal::new()).unwrap();
+        let pads = driver.take_pads().unwrap();
+        let cnt_mode = CntMode {
+            slope: TouchCountSlope::Slope3,
+            opt: TouchTieOption::High,
+        };
+        let config = TouchConfig::builder()
+            .pad_config(PadConfig {
+                cnt_mode,
+                ..PadConfig::new(TouchPadChannel::Num4, 400)
+            })
+            .build()
+            .unwrap();
+
+        let applied = config.apply(&driver, &pads).unwrap();
+        assert_eq!(applied.mismatches, vec![]);
+        assert_eq!(pads[4].get_cnt_mode().unwrap(), cnt_mode);
+    }
+
+    #[test]
+    fn apply_checks_config_and_pads() {
+        let driver = TouchDriver::new(SimTouchHal::new()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num2).unwrap();
+        let mut config = TouchConfig::builder()
+            .pad_config(PadConfig::new(TouchPadChannel::Num4, 400))
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.apply(&driver, &[pad]).err(),
+            Some(TouchError::PadMissing(TouchPadChannel::Num4))
+        );
+
+        config.pads[0].threshold = 0;
+        assert_eq!(
+            config.apply(&driver, &[]).err(),
+            Some(TouchError::InvalidConfig)
+        );
+    }
+}
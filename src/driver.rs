@@ -0,0 +1,913 @@
+use crate::channel_set::ChannelSet;
+use crate::hal::*;
+use crate::interrupt::{Subscribers, Subscription, TouchInterrupt};
+use crate::measure::Snapshot;
+use crate::pad::TouchPad;
+use crate::queue::Backoff;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+type FilterReadCallback =
+    Box<dyn FnMut(&[u16; TouchPadChannel::ALL.len()], &[u16; TouchPadChannel::ALL.len()]) + Send>;
+
+/// Longest time between `sw_start` and the drop of the done flag of the previous measurement.
+/// The FSM starts within a few cycles of the 150 kHz slow clock.
+const MEAS_START_LATENCY: Duration = Duration::from_micros(200);
+
+/// Closure of `TouchFilter::set_read_callback`. The IDF callback has no user argument, so the
+/// closure is kept here for the trampoline.
+static FILTER_READ_CALLBACK: Mutex<Option<FilterReadCallback>> = Mutex::new(None);
+
+/// The closure slot, usable even if a callback panicked while holding it.
+fn lock_filter_read_callback() -> MutexGuard<'static, Option<FilterReadCallback>> {
+    FILTER_READ_CALLBACK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Installed as the raw filter callback, forwards the arrays to `FILTER_READ_CALLBACK`.
+///
+/// The IDF passes its `raw_val` and `filtered_val` arrays, which hold `TOUCH_PAD_MAX` entries.
+/// That is 10 on the ESP32, the same as `TouchPadChannel::ALL`. The ESP32-S2 and ESP32-S3 have
+/// 15 channels but no `touch_pad_set_filter_read_cb`, so the 10 entry view is never handed a
+/// shorter array.
+unsafe extern "C" fn filter_read_trampoline(raw_value: *mut u16, filtered_value: *mut u16) {
+    if raw_value.is_null() || filtered_value.is_null() {
+        return;
+    }
+    let raw = &*(raw_value as *const [u16; TouchPadChannel::ALL.len()]);
+    let filtered = &*(filtered_value as *const [u16; TouchPadChannel::ALL.len()]);
+    if let Some(callback) = lock_filter_read_callback().as_mut() {
+        callback(raw, filtered);
+    }
+}
+
+/// Owner of the touch pad driver. The driver is initialized when the handle is created and
+/// deinitialized when it is dropped, so no touch function can be called outside of that window.
+pub struct TouchDriver<H: TouchHal> {
+    hal: H,
+    filter_running: AtomicBool,
+    /// The IDF has no getter for the interrupt enable, so it is tracked here.
+    intr_enabled: AtomicBool,
+    pads_taken: AtomicU16,
+    subscribers: Subscribers,
+}
+
+impl<H: TouchHal> TouchDriver<H> {
+    /// Initialize touch module.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_STATE the touch module is already owned by another TouchDriver
+    /// * ESP_ERR_NO_MEM Touch pad init error
+    /// * ESP_ERR_NOT_SUPPORTED Touch pad is providing current to external XTAL
+    pub fn new(hal: H) -> Result<Self, TouchError> {
+        hal.init().map_err(|err| TouchError::esp(err, "init"))?;
+        Ok(TouchDriver {
+            hal,
+            filter_running: AtomicBool::new(false),
+            intr_enabled: AtomicBool::new(false),
+            pads_taken: AtomicU16::new(0),
+            subscribers: Subscribers::default(),
+        })
+    }
+
+    /// Take ownership of one pad. Only the returned handle can configure or read the channel,
+    /// until it is dropped.
+    ///
+    /// # Errors
+    ///
+    /// * `TouchError::InvalidChannel` `touch_num` is `Max`
+    /// * `TouchError::PadTaken` the pad is already taken
+    pub fn take_pad(&self, touch_num: TouchPadChannel) -> Result<TouchPad<'_, H>, TouchError> {
+        if touch_num == TouchPadChannel::Max {
+            return Err(TouchError::InvalidChannel(touch_num));
+        }
+        let bit = 1 << touch_num as u16;
+        if self.pads_taken.fetch_or(bit, Ordering::AcqRel) & bit != 0 {
+            return Err(TouchError::PadTaken(touch_num));
+        }
+        Ok(TouchPad::new(self, touch_num))
+    }
+
+    /// Take ownership of all ten pads, indexed by `TouchPadChannel`.
+    ///
+    /// # Error
+    ///
+    /// * `TouchError::PadTaken` a pad is already taken, the error names the first one
+    pub fn take_pads(&self) -> Result<[TouchPad<'_, H>; TouchPadChannel::ALL.len()], TouchError> {
+        let all = (1 << TouchPadChannel::ALL.len()) - 1;
+        self.pads_taken
+            .compare_exchange(0, all, Ordering::AcqRel, Ordering::Acquire)
+            .map_err(|taken| {
+                TouchError::PadTaken(TouchPadChannel::ALL[taken.trailing_zeros() as usize])
+            })?;
+        Ok(TouchPadChannel::ALL.map(|touch_num| TouchPad::new(self, touch_num)))
+    }
+
+    /// Whether a `TouchPad` handle of the channel exists.
+    pub(crate) fn is_pad_taken(&self, touch_num: TouchPadChannel) -> bool {
+        self.pads_taken.load(Ordering::Acquire) & (1 << touch_num as u16) != 0
+    }
+
+    pub(crate) fn release_pad(&self, touch_num: TouchPadChannel) {
+        self.pads_taken
+            .fetch_and(!(1 << touch_num as u16), Ordering::AcqRel);
+    }
+
+    /// Trigger a touch sensor measurement, only support in SW mode of FSM.
+    pub fn sw_start(&self) -> Result<(), TouchError> {
+        self.hal
+            .sw_start()
+            .map_err(|err| TouchError::esp(err, "sw_start"))
+    }
+
+    /// Set touch sensor interrupt trigger source.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_trigger_source(&self, src: TouchTriggerSource) -> Result<(), TouchError> {
+        self.hal
+            .set_trigger_source(src)
+            .map_err(|err| TouchError::esp(err, "set_trigger_source").arg("src", src))
+    }
+
+    /// Get touch sensor interrupt trigger source.
+    pub fn get_trigger_source(&self) -> Result<TouchTriggerSource, TouchError> {
+        self.hal
+            .get_trigger_source()
+            .map_err(|err| TouchError::esp(err, "get_trigger_source"))
+    }
+
+    /// Set touch sensor interrupt trigger mode.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_trigger_mode(&self, mode: TouchTriggerMode) -> Result<(), TouchError> {
+        self.hal
+            .set_trigger_mode(mode)
+            .map_err(|err| TouchError::esp(err, "set_trigger_mode").arg("mode", mode))
+    }
+
+    /// Get touch sensor interrupt trigger mode.
+    pub fn get_trigger_mode(&self) -> Result<TouchTriggerMode, TouchError> {
+        self.hal
+            .get_trigger_mode()
+            .map_err(|err| TouchError::esp(err, "get_trigger_mode"))
+    }
+
+    /// Set touch sensor interrupt threshold.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub(crate) fn set_thresh(
+        &self,
+        touch_num: TouchPadChannel,
+        threshold: u16,
+    ) -> Result<(), TouchError> {
+        self.hal.set_thresh(touch_num, threshold).map_err(|err| {
+            TouchError::esp(err, "set_thresh")
+                .on(touch_num)
+                .arg("threshold", threshold)
+        })
+    }
+
+    /// Get touch sensor interrupt threshold.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub(crate) fn get_thresh(&self, touch_num: TouchPadChannel) -> Result<u16, TouchError> {
+        self.hal
+            .get_thresh(touch_num)
+            .map_err(|err| TouchError::esp(err, "get_thresh").on(touch_num))
+    }
+
+    /// Set touch sensor measurement and sleep time.
+    pub fn set_meas_time(&self, sleep_cycle: u16, meas_cycle: u16) -> Result<(), TouchError> {
+        self.hal
+            .set_meas_time(sleep_cycle, meas_cycle)
+            .map_err(|err| {
+                TouchError::esp(err, "set_meas_time")
+                    .arg("sleep_cycle", sleep_cycle)
+                    .arg("meas_cycle", meas_cycle)
+            })
+    }
+
+    /// Get touch sensor measurement and sleep time.
+    pub fn get_meas_time(&self) -> Result<MeasTime, TouchError> {
+        self.hal
+            .get_meas_time()
+            .map_err(|err| TouchError::esp(err, "get_meas_time"))
+    }
+
+    /// Set the interval between two measurements.
+    pub fn set_measurement_interval(&self, interval_cycle: u16) -> Result<(), TouchError> {
+        self.hal
+            .set_measurement_interval(interval_cycle)
+            .map_err(|err| {
+                TouchError::esp(err, "set_measurement_interval")
+                    .arg("interval_cycle", interval_cycle)
+            })
+    }
+
+    /// Get the interval between two measurements.
+    pub fn get_measurement_interval(&self) -> Result<u16, TouchError> {
+        self.hal
+            .get_measurement_interval()
+            .map_err(|err| TouchError::esp(err, "get_measurement_interval"))
+    }
+
+    /// Set the clock cycles of each measurement.
+    pub fn set_measurement_clock_cycles(&self, clock_cycle: u16) -> Result<(), TouchError> {
+        self.hal
+            .set_measurement_clock_cycles(clock_cycle)
+            .map_err(|err| {
+                TouchError::esp(err, "set_measurement_clock_cycles").arg("clock_cycle", clock_cycle)
+            })
+    }
+
+    /// Get the clock cycles of each measurement.
+    pub fn get_measurement_clock_cycles(&self) -> Result<u16, TouchError> {
+        self.hal
+            .get_measurement_clock_cycles()
+            .map_err(|err| TouchError::esp(err, "get_measurement_clock_cycles"))
+    }
+
+    /// Set touch sensor reference voltages.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_voltage(
+        &self,
+        touch_high_voltage: TouchHighVoltage,
+        touch_low_voltage: TouchLowVoltage,
+        touch_voltage_attenuation: TouchVoltageAttenuation,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .set_voltage(
+                touch_high_voltage,
+                touch_low_voltage,
+                touch_voltage_attenuation,
+            )
+            .map_err(|err| {
+                TouchError::esp(err, "set_voltage")
+                    .arg("touch_high_voltage", touch_high_voltage)
+                    .arg("touch_low_voltage", touch_low_voltage)
+                    .arg("touch_voltage_attenuation", touch_voltage_attenuation)
+            })
+    }
+
+    /// Get touch sensor reference voltages.
+    pub fn get_voltage(&self) -> Result<VoltageConfig, TouchError> {
+        self.hal
+            .get_voltage()
+            .map_err(|err| TouchError::esp(err, "get_voltage"))
+    }
+
+    /// Configure touch pad interrupt threshold.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument wrong
+    pub(crate) fn config(
+        &self,
+        touch_num: TouchPadChannel,
+        threshold: u16,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .config(touch_num, threshold)
+            .map_err(|err| match err {
+                EspErr::EspFail => TouchError::NotInitialized,
+                err => TouchError::esp(err, "config")
+                    .on(touch_num)
+                    .arg("threshold", threshold),
+            })
+    }
+
+    /// get touch sensor counter value.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG Touch pad parameter error
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection This touch pad hardware connection is error, the value of "touch_value" is 0.
+    pub(crate) fn read(&self, touch_num: TouchPadChannel) -> Result<u16, TouchError> {
+        self.hal
+            .read(touch_num)
+            .map_err(|err| self.read_error(err, "read", touch_num))
+    }
+
+    /// get raw data (touch sensor counter value) from IIR filter process.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG Touch pad parameter error
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection This touch pad hardware connection is error, the value of "touch_value" is 0.
+    pub(crate) fn read_raw_data(&self, touch_num: TouchPadChannel) -> Result<u16, TouchError> {
+        self.hal
+            .read_raw_data(touch_num)
+            .map_err(|err| self.read_error(err, "read_raw_data", touch_num))
+    }
+
+    /// get filtered touch sensor counter value by IIR filter.
+    ///
+    /// # Errors
+    ///
+    /// * FilterNotStarted the filter is not running
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection the touch pad hardware connection is error
+    pub(crate) fn read_filtered(&self, touch_num: TouchPadChannel) -> Result<u16, TouchError> {
+        if !self.filter_running.load(Ordering::Acquire) {
+            return Err(TouchError::FilterNotStarted);
+        }
+        self.hal
+            .read_filtered(touch_num)
+            .map_err(|err| self.read_error(err, "read_filtered", touch_num))
+    }
+
+    /// Error of a counter read. The IDF returns ESP_FAIL before init and ESP_ERR_INVALID_STATE
+    /// when the channel reads 0, either because it is not enabled or because its connection is
+    /// broken.
+    fn read_error(
+        &self,
+        err: EspErr,
+        operation: &'static str,
+        touch_num: TouchPadChannel,
+    ) -> TouchError {
+        match err {
+            EspErr::EspFail => TouchError::NotInitialized,
+            EspErr::EspErrInvalidState => match self.hal.get_group_mask() {
+                Ok(group_mask) if !group_mask.en_mask.contains(touch_num) => {
+                    TouchError::ChannelNotEnabled(touch_num)
+                }
+                _ => TouchError::BadConnection(touch_num),
+            },
+            err => TouchError::esp(err, operation).on(touch_num),
+        }
+    }
+
+    /// Start the IIR filter. The filter runs until the returned guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_STATE the filter is already running
+    /// * ESP_ERR_INVALID_ARG parameter error
+    /// * ESP_ERR_NO_MEM No memory for driver
+    pub fn filter_start(&self, filter_period_ms: u32) -> Result<TouchFilter<'_, H>, TouchError> {
+        let error =
+            |err| TouchError::esp(err, "filter_start").arg("filter_period_ms", filter_period_ms);
+        if self.filter_running.swap(true, Ordering::AcqRel) {
+            return Err(error(EspErr::EspErrInvalidState));
+        }
+        if let Err(err) = self.hal.filter_start(filter_period_ms) {
+            self.filter_running.store(false, Ordering::Release);
+            return Err(error(err));
+        }
+        Ok(TouchFilter {
+            driver: self,
+            read_callback: AtomicBool::new(false),
+        })
+    }
+
+    /// Filter calibration period, `None` when the filter is not running.
+    pub(crate) fn filter_period(&self) -> Result<Option<u32>, TouchError> {
+        if !self.filter_running.load(Ordering::Acquire) {
+            return Ok(None);
+        }
+        self.hal
+            .get_filter_period()
+            .map(Some)
+            .map_err(|err| TouchError::esp(err, "get_filter_period"))
+    }
+
+    /// Set the filter calibration period if the filter is running, returns whether it was.
+    pub(crate) fn set_running_filter_period(&self, period_ms: u32) -> Result<bool, TouchError> {
+        if !self.filter_running.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+        self.hal.set_filter_period(period_ms).map_err(|err| {
+            TouchError::esp(err, "set_filter_period").arg("new_period_ms", period_ms)
+        })?;
+        Ok(true)
+    }
+
+    /// Set touch sensor group mask.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .set_group_mask(set1_mask, set2_mask, en_mask)
+            .map_err(|err| {
+                TouchError::esp(err, "set_group_mask")
+                    .arg("set1_mask", set1_mask)
+                    .arg("set2_mask", set2_mask)
+                    .arg("en_mask", en_mask)
+            })
+    }
+
+    /// Get touch sensor group mask.
+    pub fn get_group_mask(&self) -> Result<GroupMask, TouchError> {
+        self.hal
+            .get_group_mask()
+            .map_err(|err| TouchError::esp(err, "get_group_mask"))
+    }
+
+    /// Clear touch sensor group mask.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn clear_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .clear_group_mask(set1_mask, set2_mask, en_mask)
+            .map_err(|err| {
+                TouchError::esp(err, "clear_group_mask")
+                    .arg("set1_mask", set1_mask)
+                    .arg("set2_mask", set2_mask)
+                    .arg("en_mask", en_mask)
+            })
+    }
+
+    /// To enable touch pad interrupt.
+    pub fn intr_enable(&self) -> Result<(), TouchError> {
+        self.hal
+            .intr_enable()
+            .map_err(|err| TouchError::esp(err, "intr_enable"))?;
+        self.intr_enabled.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// To disable touch pad interrupt.
+    pub fn intr_disable(&self) -> Result<(), TouchError> {
+        self.hal
+            .intr_disable()
+            .map_err(|err| TouchError::esp(err, "intr_disable"))?;
+        self.intr_enabled.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    /// Whether the touch pad interrupt is enabled. The driver starts with it disabled.
+    pub fn is_intr_enabled(&self) -> bool {
+        self.intr_enabled.load(Ordering::Acquire)
+    }
+
+    /// To clear touch pad interrupt.
+    pub fn intr_clear(&self) -> Result<(), TouchError> {
+        self.hal
+            .intr_clear()
+            .map_err(|err| TouchError::esp(err, "intr_clear"))
+    }
+
+    /// Initialize touch pad GPIO.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub(crate) fn pad_io_init(&self, touch_num: TouchPadChannel) -> Result<(), TouchError> {
+        self.hal
+            .pad_io_init(touch_num)
+            .map_err(|err| TouchError::esp(err, "pad_io_init").on(touch_num))
+    }
+
+    /// Set touch sensor charge/discharge speed for each pad.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub(crate) fn set_cnt_mode(
+        &self,
+        touch_num: TouchPadChannel,
+        slope: TouchCountSlope,
+        opt: TouchTieOption,
+    ) -> Result<(), TouchError> {
+        self.hal.set_cnt_mode(touch_num, slope, opt).map_err(|err| {
+            TouchError::esp(err, "set_cnt_mode")
+                .on(touch_num)
+                .arg("slope", slope)
+                .arg("opt", opt)
+        })
+    }
+
+    /// Get touch sensor charge/discharge speed for each pad.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub(crate) fn get_cnt_mode(&self, touch_num: TouchPadChannel) -> Result<CntMode, TouchError> {
+        self.hal
+            .get_cnt_mode(touch_num)
+            .map_err(|err| TouchError::esp(err, "get_cnt_mode").on(touch_num))
+    }
+
+    /// Get the touch pad which caused wakeup from deep sleep.
+    pub fn get_wakeup_status(&self) -> Result<TouchPadChannel, TouchError> {
+        self.hal
+            .get_wakeup_status()
+            .map_err(|err| TouchError::esp(err, "get_wakeup_status"))
+    }
+
+    /// Set touch sensor FSM mode.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_fsm_mode(&self, mode: TouchFSMMode) -> Result<(), TouchError> {
+        self.hal
+            .set_fsm_mode(mode)
+            .map_err(|err| TouchError::esp(err, "set_fsm_mode").arg("mode", mode))
+    }
+
+    /// Get touch sensor FSM mode.
+    pub fn get_fsm_mode(&self) -> Result<TouchFSMMode, TouchError> {
+        self.hal
+            .get_fsm_mode()
+            .map_err(|err| TouchError::esp(err, "get_fsm_mode"))
+    }
+
+    /// To clear the touch sensor channel active status.
+    pub fn clear_status(&self) -> Result<(), TouchError> {
+        self.hal
+            .clear_status()
+            .map_err(|err| TouchError::esp(err, "clear_status"))
+    }
+
+    /// Get the touch sensor channel active status.
+    pub fn get_status(&self) -> ChannelSet {
+        self.hal.get_status()
+    }
+
+    /// Check touch sensor measurement status.
+    pub fn meas_is_done(&self) -> bool {
+        self.hal.meas_is_done()
+    }
+
+    /// Milliseconds since boot, the clock of the interrupt timestamps.
+    pub fn timestamp_ms(&self) -> u32 {
+        self.hal.timestamp_ms()
+    }
+
+    /// Run one software-triggered measurement and wait for it to complete. The FSM is switched to
+    /// SW mode first if needed. The wait backs off from spinning to sleeping.
+    ///
+    /// The done flag of the previous measurement only drops when the FSM starts the new one, a
+    /// few slow clock cycles after `sw_start`. The flag is given `MEAS_START_LATENCY` to drop
+    /// before it is trusted, a measurement short enough to be over by then being done anyway.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_TIMEOUT the measurement did not complete within `timeout`
+    pub fn measure_once(&self, timeout: Duration) -> Result<(), TouchError> {
+        if self.get_fsm_mode()? != TouchFSMMode::SW {
+            self.set_fsm_mode(TouchFSMMode::SW)?;
+        }
+        let start = Instant::now();
+        self.sw_start()?;
+        let settle = MEAS_START_LATENCY.min(timeout);
+        while self.hal.meas_is_done() && start.elapsed() < settle {
+            std::hint::spin_loop();
+        }
+        let mut backoff = Backoff::default();
+        while !self.hal.meas_is_done() {
+            if start.elapsed() >= timeout {
+                return Err(
+                    TouchError::esp(EspErr::EspErrTimeout, "measure_once").arg("timeout", timeout)
+                );
+            }
+            backoff.wait();
+        }
+        Ok(())
+    }
+
+    /// Run one software-triggered measurement like `measure_once` and read every enabled
+    /// channel.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_TIMEOUT the measurement did not complete within `timeout`
+    pub fn measure_all(&self, timeout: Duration) -> Result<Snapshot, TouchError> {
+        self.measure_once(timeout)?;
+        let enabled = self.get_group_mask()?.en_mask;
+        let mut values = [None; TouchPadChannel::ALL.len()];
+        for touch_num in enabled {
+            values[touch_num as usize] = match self.hal.read(touch_num) {
+                Ok(value) => Some(value),
+                Err(EspErr::EspErrInvalidState) => None,
+                Err(err) => return Err(self.read_error(err, "read", touch_num)),
+            };
+        }
+        Ok(Snapshot {
+            timestamp_ms: self.hal.timestamp_ms(),
+            status: self.hal.get_status(),
+            values,
+        })
+    }
+
+    /// Register touch-pad ISR.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG GPIO error
+    /// * ESP_ERR_NO_MEM No memory
+    pub fn isr_register(
+        &self,
+        isr_handler: IntrHandler,
+        arg: *mut c_void,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .isr_register(isr_handler, arg)
+            .map_err(|err| TouchError::esp(err, "isr_register"))
+    }
+
+    /// Deregister the handler previously registered using isr_register.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_STATE if a handler matching both fn and arg isn't registered
+    pub fn isr_deregister(
+        &self,
+        isr_handler: IntrHandler,
+        arg: *mut c_void,
+    ) -> Result<(), TouchError> {
+        self.hal
+            .isr_deregister(isr_handler, arg)
+            .map_err(|err| TouchError::esp(err, "isr_deregister"))
+    }
+}
+
+impl<H: TouchHal + Sync> TouchDriver<H> {
+    /// Run `callback` on every touch interrupt.
+    ///
+    /// The driver registers a single ISR, whatever the number of subscriptions. It reads the
+    /// active channels, clears the status and interrupt, then calls every callback with the same
+    /// `TouchInterrupt`. Adding or dropping a subscription does not stop the ISR, but waits for
+    /// a running ISR to finish. The callbacks run in the ISR, so they have to be short and must
+    /// not block.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG GPIO error
+    /// * ESP_ERR_NO_MEM No memory
+    pub fn subscribe<F>(&self, callback: F) -> Result<Subscription<'_, H>, TouchError>
+    where
+        F: FnMut(TouchInterrupt) + Send + 'static,
+    {
+        Subscription::new(self, Box::new(callback))
+    }
+
+    pub(crate) fn subscribers(&self) -> &Subscribers {
+        &self.subscribers
+    }
+}
+
+impl<H: TouchHal> Drop for TouchDriver<H> {
+    fn drop(&mut self) {
+        let _ = self.hal.deinit();
+    }
+}
+
+/// Running IIR filter. It borrows the driver so it can never outlive it, and the filter is
+/// stopped and deleted when the guard is dropped.
+pub struct TouchFilter<'d, H: TouchHal> {
+    driver: &'d TouchDriver<H>,
+    /// Whether this filter installed the closure of `FILTER_READ_CALLBACK`.
+    read_callback: AtomicBool,
+}
+
+impl<H: TouchHal> TouchFilter<'_, H> {
+    /// set touch pad filter calibration period, in ms.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG parameter error
+    pub fn set_filter_period(&self, new_period_ms: u32) -> Result<(), TouchError> {
+        self.driver
+            .hal
+            .set_filter_period(new_period_ms)
+            .map_err(|err| {
+                TouchError::esp(err, "set_filter_period").arg("new_period_ms", new_period_ms)
+            })
+    }
+
+    /// get touch pad filter calibration period, in ms.
+    pub fn get_filter_period(&self) -> Result<u32, TouchError> {
+        self.driver
+            .hal
+            .get_filter_period()
+            .map_err(|err| TouchError::esp(err, "get_filter_period"))
+    }
+
+    /// Register the callback function that is called after each IIR filter calculation.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG set error
+    pub fn set_filter_read_cb(&self, filter_cb: FilterCbT) -> Result<(), TouchError> {
+        self.driver
+            .hal
+            .set_filter_read_cb(filter_cb)
+            .map_err(|err| TouchError::esp(err, "set_filter_read_cb"))
+    }
+
+    /// Run `callback` after each IIR filter calculation with the raw and filtered values of
+    /// every channel, indexed by `TouchPadChannel`. It replaces any callback set before and is
+    /// removed when the filter is dropped. There is a single filter callback in the process.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG set error
+    pub fn set_read_callback<F>(&self, callback: F) -> Result<(), TouchError>
+    where
+        F: FnMut(&[u16; TouchPadChannel::ALL.len()], &[u16; TouchPadChannel::ALL.len()])
+            + Send
+            + 'static,
+    {
+        *lock_filter_read_callback() = Some(Box::new(callback));
+        self.read_callback.store(true, Ordering::Release);
+        self.set_filter_read_cb(Some(filter_read_trampoline))
+    }
+
+    /// Remove the callback set with `set_read_callback` or `set_filter_read_cb`.
+    pub fn clear_read_callback(&self) -> Result<(), TouchError> {
+        self.set_filter_read_cb(None)?;
+        if self.read_callback.swap(false, Ordering::AcqRel) {
+            *lock_filter_read_callback() = None;
+        }
+        Ok(())
+    }
+
+    /// The driver this filter runs on.
+    pub fn driver(&self) -> &TouchDriver<H> {
+        self.driver
+    }
+}
+
+impl<H: TouchHal> Drop for TouchFilter<'_, H> {
+    fn drop(&mut self) {
+        let _ = self.driver.hal.set_filter_read_cb(None);
+        let _ = self.driver.hal.filter_stop();
+        let _ = self.driver.hal.filter_delete();
+        if self.read_callback.load(Ordering::Acquire) {
+            *lock_filter_read_callback() = None;
+        }
+        self.driver.filter_running.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{SimTouchHal, SIM_DEFAULT_RAW};
+    use std::sync::Arc;
+
+    #[test]
+    fn init_twice_is_rejected() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let err = TouchDriver::new(sim.clone()).err().unwrap();
+        assert_eq!(err.esp_err(), EspErr::EspErrInvalidState);
+
+        drop(driver);
+        assert!(!sim.is_initialized());
+        assert!(TouchDriver::new(sim).is_ok());
+    }
+
+    #[test]
+    fn pad_is_taken_once() {
+        let driver = TouchDriver::new(SimTouchHal::new()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num2).unwrap();
+        let err = driver.take_pad(TouchPadChannel::Num2).err().unwrap();
+        assert_eq!(err, TouchError::PadTaken(TouchPadChannel::Num2));
+        assert_eq!(
+            driver.take_pads().err(),
+            Some(TouchError::PadTaken(TouchPadChannel::Num2))
+        );
+        assert_eq!(
+            driver.take_pad(TouchPadChannel::Max).err(),
+            Some(TouchError::InvalidChannel(TouchPadChannel::Max))
+        );
+
+        drop(pad);
+        let pads = driver.take_pads().unwrap();
+        assert!(driver.take_pad(TouchPadChannel::Num9).is_err());
+        drop(pads);
+        assert!(driver.take_pad(TouchPadChannel::Num2).is_ok());
+    }
+
+    #[test]
+    fn read_errors_name_the_cause() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num4).unwrap();
+        assert_eq!(
+            pad.read(),
+            Err(TouchError::ChannelNotEnabled(TouchPadChannel::Num4))
+        );
+
+        pad.configure(500).unwrap();
+        assert_eq!(pad.read(), Ok(SIM_DEFAULT_RAW));
+        let filter = driver.filter_start(10).unwrap();
+        assert_eq!(pad.read_filtered(), Ok(SIM_DEFAULT_RAW));
+
+        sim.set_raw(TouchPadChannel::Num4, 0);
+        assert_eq!(
+            pad.read_raw_data(),
+            Err(TouchError::BadConnection(TouchPadChannel::Num4))
+        );
+
+        sim.set_raw(TouchPadChannel::Num4, SIM_DEFAULT_RAW);
+        let channel = ChannelSet::from(TouchPadChannel::Num4);
+        driver
+            .clear_group_mask(ChannelSet::new(), ChannelSet::new(), channel)
+            .unwrap();
+        assert_eq!(
+            pad.read_filtered(),
+            Err(TouchError::ChannelNotEnabled(TouchPadChannel::Num4))
+        );
+        drop(filter);
+    }
+
+    #[test]
+    fn read_callback_gets_the_filtered_values() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num3).unwrap();
+        pad.configure(500).unwrap();
+        sim.set_raw(TouchPadChannel::Num3, 900);
+        let filter = driver.filter_start(10).unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        filter
+            .set_read_callback(move |raw, filtered| {
+                let _ = sender.send((*raw, *filtered));
+            })
+            .unwrap();
+        assert!(sim.has_filter_read_cb());
+
+        sim.tick();
+        let (raw, filtered) = receiver.try_recv().unwrap();
+        let mut expected = [0; TouchPadChannel::ALL.len()];
+        expected[TouchPadChannel::Num3 as usize] = 900;
+        assert_eq!(raw, expected);
+        assert_eq!(filtered, expected);
+
+        drop(filter);
+        assert!(!sim.has_filter_read_cb());
+        assert!(lock_filter_read_callback().is_none());
+        sim.tick();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn subscription_gets_interrupts() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num7).unwrap();
+        pad.configure(500).unwrap();
+        sim.advance_ms(42);
+
+        let interrupts = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&interrupts);
+        let subscription = driver
+            .subscribe(move |interrupt| seen.lock().unwrap().push(interrupt))
+            .unwrap();
+        sim.set_raw(TouchPadChannel::Num7, 100);
+        sim.tick();
+        assert!(interrupts.lock().unwrap().is_empty());
+
+        driver.intr_enable().unwrap();
+        sim.tick();
+        assert_eq!(
+            *interrupts.lock().unwrap(),
+            vec![TouchInterrupt {
+                status_mask: ChannelSet::from(TouchPadChannel::Num7),
+                timestamp: 42,
+            }]
+        );
+
+        drop(subscription);
+        assert_eq!(sim.isr_count(), 0);
+        sim.tick();
+        assert_eq!(interrupts.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,609 @@
+use crate::channel_set::ChannelSet;
+use crate::hal::*;
+use crate::safe;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use esp_idf_svc::sys::*;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Initialize touch module.
+///
+/// # Error
+///
+/// * ESP_ERR_NO_MEM Touch pad init error
+/// * ESP_ERR_NOT_SUPPORTED Touch pad is providing current to external XTAL
+fn init() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_init()) }
+}
+
+/// Deinitialize touch module.
+///
+/// # Error
+///
+/// * ESP_FAIL Touch pad driver not initialized
+fn deinit() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_deinit()) }
+}
+
+/// Trigger a touch sensor measurement, only support in SW mode of FSM.
+fn sw_start() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_sw_start()) }
+}
+
+/// Set touch sensor interrupt trigger source. There are two sets of touch signals. Set1 and set2 can be mapped to several touch signals.
+/// Either set will be triggered if at least one of its touch signal is 'touched'.
+/// The interrupt can be configured to be generated if set1 is triggered, or only if both sets are triggered.
+///
+/// # Arguments
+///
+/// * `src` - TouchTriggerSource.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn set_trigger_source(src: TouchTriggerSource) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_trigger_source(src as u32)) }
+}
+
+/// Set touch sensor interrupt trigger mode. Interrupt can be triggered either when counter result is less than threshold or when counter result is more than threshold.
+///
+/// # Arguments
+///
+/// * `mode` - TouchTriggerMode.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn set_trigger_mode(mode: TouchTriggerMode) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_trigger_mode(mode as u32)) }
+}
+
+/// Set touch sensor interrupt threshold.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+/// * `threshold` - u16.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn set_thresh(touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_thresh(touch_num as u32, threshold)) }
+}
+
+/// Set touch sensor measurement and sleep time. Excessive total time will slow down the touch response.
+/// Too small measurement time will not be sampled enough, resulting in inaccurate measurements.
+///
+/// # Arguments
+///
+/// * `sleep_cycle` - u16.
+/// * `meas_cycle` - u16.
+fn set_meas_time(sleep_cycle: u16, meas_cycle: u16) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_meas_time(sleep_cycle, meas_cycle)) }
+}
+
+/// Set the interval between two measurements.
+///
+/// # Arguments
+///
+/// * `interval_cycle` - u16.
+fn set_measurement_interval(interval_cycle: u16) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_measurement_interval(interval_cycle)) }
+}
+
+/// Register the callback function that is called after each IIR filter calculation.
+///
+/// # Arguments
+///
+/// * `filter_cb` - FilterCbT.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG set error
+fn set_filter_read_cb(filter_cb: FilterCbT) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_filter_read_cb(filter_cb)) }
+}
+
+/// Set touch sensor high voltage threshold of chanrge. The touch sensor measures the channel capacitance value by charging and discharging the channel.
+/// So the high threshold should be less than the supply voltage.
+///
+/// # Arguments
+///
+/// * `touch_high_voltage` - TouchHighVoltage.
+/// * `touch_low_voltage` - TouchLowVoltage.
+/// * `touch_voltage_attenuation` - TouchVoltageAttenuation.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn set_voltage(
+    touch_high_voltage: TouchHighVoltage,
+    touch_low_voltage: TouchLowVoltage,
+    touch_voltage_attenuation: TouchVoltageAttenuation,
+) -> Result<(), EspErr> {
+    unsafe {
+        EspErr::return_message(touch_pad_set_voltage(
+            touch_high_voltage as i32,
+            touch_low_voltage as i32,
+            touch_voltage_attenuation as i32,
+        ))
+    }
+}
+
+/// Configure touch pad interrupt threshold.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+/// * `threshold` - u16.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG if argument wrong
+/// * ESP_FAIL if touch pad not initialized
+fn config(touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_config(touch_num as u32, threshold)) }
+}
+
+/// start touch pad filter function This API will start a filter to process the noise in order to prevent false triggering when detecting slight change of capacitance.
+/// Need to call touch_pad_filter_start before all touch filter APIs
+///
+/// # Arguments
+///
+/// * `filter_period_ms` - u32.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG parameter error
+/// * ESP_ERR_NO_MEM No memory for driver
+/// * ESP_ERR_INVALID_STATE driver state error
+fn filter_start(filter_period_ms: u32) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_filter_start(filter_period_ms)) }
+}
+
+/// Set the clock cycles of each measurement.
+///
+/// # Arguments
+///
+/// * `clock_cycle` - u16.
+///
+/// # Note
+///
+/// This function will specify the clock cycles of each measurement and the clock is sourced from SOC_MOD_CLK_RTC_FAST, its default frequency is SOC_CLK_RC_FAST_FREQ_APPROX The touch sensor will record the charge and discharge times during these clock cycles as the final result (raw value)
+/// If clock cyles is too small, it may lead to inaccurate results.
+fn set_measurement_clock_cycles(clock_cycle: u16) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_measurement_clock_cycles(clock_cycle)) }
+}
+
+/// Set touch sensor group mask. Touch pad module has two sets of signals, 'Touched' signal is triggered only if at least one of touch pad in this group is \"touched\".
+/// This function will set the register bits according to the given bitmask.
+///
+/// # Arguments
+///
+/// * set1_mask -- bitmask touch sensor signal group1,
+/// * set2_mask -- bitmask touch sensor signal group2,
+/// * en_mask -- bitmask of touch sensor work enable,
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn set_group_mask(
+    set1_mask: ChannelSet,
+    set2_mask: ChannelSet,
+    en_mask: ChannelSet,
+) -> Result<(), EspErr> {
+    unsafe {
+        EspErr::return_message(touch_pad_set_group_mask(
+            set1_mask.bits(),
+            set2_mask.bits(),
+            en_mask.bits(),
+        ))
+    }
+}
+
+/// Clear touch sensor group mask. Touch pad module has two sets of signals, Interrupt is triggered only if at least one of touch pad in this group is \"touched\".
+/// This function will clear the register bits according to the given bitmask.
+///
+/// # Arguments
+///
+/// * set1_mask -- bitmask touch sensor signal group1,
+/// * set2_mask -- bitmask touch sensor signal group2,
+/// * en_mask -- bitmask of touch sensor work enable,
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn clear_group_mask(
+    set1_mask: ChannelSet,
+    set2_mask: ChannelSet,
+    en_mask: ChannelSet,
+) -> Result<(), EspErr> {
+    unsafe {
+        EspErr::return_message(touch_pad_clear_group_mask(
+            set1_mask.bits(),
+            set2_mask.bits(),
+            en_mask.bits(),
+        ))
+    }
+}
+
+/// To enable touch pad interrupt.
+///
+/// # Error
+///
+/// * ESP_OK on success
+fn intr_enable() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_intr_enable()) }
+}
+
+/// To disable touch pad interrupt.
+fn intr_disable() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_intr_disable()) }
+}
+
+/// To clear touch pad interrupt.
+fn intr_clear() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_intr_clear()) }
+}
+
+/// set touch pad filter calibration period, in ms. Need to call touch_pad_filter_start before all touch filter APIs
+///
+/// # Arguments
+///
+/// * `p_period_ms` - u32.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_STATE driver state error
+/// * ESP_ERR_INVALID_ARG parameter error
+fn set_filter_period(new_period_ms: u32) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_filter_period(new_period_ms)) }
+}
+
+/// stop touch pad filter function Need to call touch_pad_filter_start before all touch filter APIs
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_STATE driver state error
+fn filter_stop() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_filter_stop()) }
+}
+
+/// delete touch pad filter driver and release the memory Need to call touch_pad_filter_start before all touch filter APIs
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_STATE driver state error
+fn filter_delete() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_filter_delete()) }
+}
+
+/// Initialize touch pad GPIO.
+///
+/// # Arguments
+///
+/// * `src` - TouchTriggerSource.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+fn pad_io_init(touch_num: TouchPadChannel) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_io_init(touch_num as u32)) }
+}
+
+/// Set touch sensor charge/discharge speed for each pad. If the slope is 0, the counter would always be zero. If the slope is 1,
+/// the charging and discharging would be slow, accordingly. If the slope is set 7, which is the maximum value, the charging and discharging would be fast.
+///
+/// # Arguments
+/// touch_num -- TouchPadChannel
+/// slope -- TouchCountSlope
+/// opt -- TouchTieOption
+///
+/// # Error
+///
+/// ESP_ERR_INVALID_ARG if argument is wrong
+fn set_cnt_mode(
+    touch_num: TouchPadChannel,
+    slope: TouchCountSlope,
+    opt: TouchTieOption,
+) -> Result<(), EspErr> {
+    unsafe {
+        EspErr::return_message(touch_pad_set_cnt_mode(
+            touch_num as u32,
+            slope as u32,
+            opt as u32,
+        ))
+    }
+}
+
+/// Set touch sensor FSM mode, the test action can be triggered by the timer, as well as by the software.
+///
+/// # Arguments
+///
+/// * `mode` - TouchFSMMode.
+///
+/// # Error
+///
+/// ESP_ERR_INVALID_ARG if argument is wrong
+fn set_fsm_mode(mode: TouchFSMMode) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_set_fsm_mode(mode as u32)) }
+}
+
+/// To clear the touch sensor channel active status.
+fn clear_status() -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_clear_status()) }
+}
+
+/// Get the touch sensor channel active status mask. The bit position represents the channel number.
+/// The 0/1 status of the bit represents the trigger status.
+///
+/// # Error
+///
+/// * u32
+fn get_status() -> ChannelSet {
+    ChannelSet::from_bits_truncate(unsafe { touch_pad_get_status() })
+}
+
+/// Check touch sensor measurement status.
+///
+/// # Error
+///
+/// * bool
+fn meas_is_done() -> bool {
+    unsafe { touch_pad_meas_is_done() }
+}
+
+/// Milliseconds since boot, from the ISR safe esp_timer.
+fn timestamp_ms() -> u32 {
+    (unsafe { esp_timer_get_time() } / 1000) as u32
+}
+
+/// Register touch-pad ISR. The handler is attached to the CPU core this function runs on, so
+/// register it from the core that should service the touch interrupt.
+///
+/// # Arguments
+///
+/// * `isr_handler` - IntrHandler.
+/// * `arg` - Parameter for ISR.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG GPIO error
+/// * ESP_ERR_NO_MEM No memory
+fn isr_register(isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_isr_register(isr_handler, arg)) }
+}
+
+/// Deregister the handler previously registered using isr_register.
+///
+/// # Arguments
+///
+/// * `isr_handler` - handler function to call (as passed to isr_register).
+/// * `arg` - argument of the handler (as passed to isr_register).
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_STATE if a handler matching both fn and arg isn't registered
+fn isr_deregister(isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+    unsafe { EspErr::return_message(touch_pad_isr_deregister(isr_handler, arg)) }
+}
+
+static TOUCH_PAD_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// `TouchHal` backed by the ESP-IDF touch pad driver. There is a single touch peripheral,
+/// so only one initialized `EspTouchHal` can exist at a time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EspTouchHal;
+
+impl TouchHal for EspTouchHal {
+    fn init(&self) -> Result<(), EspErr> {
+        if TOUCH_PAD_TAKEN.swap(true, Ordering::AcqRel) {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        init().inspect_err(|_| TOUCH_PAD_TAKEN.store(false, Ordering::Release))
+    }
+
+    fn deinit(&self) -> Result<(), EspErr> {
+        deinit()?;
+        TOUCH_PAD_TAKEN.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    fn sw_start(&self) -> Result<(), EspErr> {
+        sw_start()
+    }
+
+    fn set_trigger_source(&self, src: TouchTriggerSource) -> Result<(), EspErr> {
+        set_trigger_source(src)
+    }
+
+    fn get_trigger_source(&self) -> Result<TouchTriggerSource, EspErr> {
+        safe::get_trigger_source()
+    }
+
+    fn set_trigger_mode(&self, mode: TouchTriggerMode) -> Result<(), EspErr> {
+        set_trigger_mode(mode)
+    }
+
+    fn get_trigger_mode(&self) -> Result<TouchTriggerMode, EspErr> {
+        safe::get_trigger_mode()
+    }
+
+    fn set_thresh(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+        set_thresh(touch_num, threshold)
+    }
+
+    fn get_thresh(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        safe::get_thresh(touch_num)
+    }
+
+    fn set_meas_time(&self, sleep_cycle: u16, meas_cycle: u16) -> Result<(), EspErr> {
+        set_meas_time(sleep_cycle, meas_cycle)
+    }
+
+    fn get_meas_time(&self) -> Result<MeasTime, EspErr> {
+        safe::get_meas_time()
+    }
+
+    fn set_measurement_interval(&self, interval_cycle: u16) -> Result<(), EspErr> {
+        set_measurement_interval(interval_cycle)
+    }
+
+    fn get_measurement_interval(&self) -> Result<u16, EspErr> {
+        safe::get_measurement_interval()
+    }
+
+    fn set_measurement_clock_cycles(&self, clock_cycle: u16) -> Result<(), EspErr> {
+        set_measurement_clock_cycles(clock_cycle)
+    }
+
+    fn get_measurement_clock_cycles(&self) -> Result<u16, EspErr> {
+        safe::get_measurement_clock_cycles()
+    }
+
+    fn set_voltage(
+        &self,
+        touch_high_voltage: TouchHighVoltage,
+        touch_low_voltage: TouchLowVoltage,
+        touch_voltage_attenuation: TouchVoltageAttenuation,
+    ) -> Result<(), EspErr> {
+        set_voltage(
+            touch_high_voltage,
+            touch_low_voltage,
+            touch_voltage_attenuation,
+        )
+    }
+
+    fn get_voltage(&self) -> Result<VoltageConfig, EspErr> {
+        safe::get_voltage()
+    }
+
+    fn config(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+        config(touch_num, threshold)
+    }
+
+    fn read(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        safe::read(touch_num)
+    }
+
+    fn read_filtered(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        safe::read_filtered(touch_num)
+    }
+
+    fn read_raw_data(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        safe::read_raw_data(touch_num)
+    }
+
+    fn filter_start(&self, filter_period_ms: u32) -> Result<(), EspErr> {
+        filter_start(filter_period_ms)
+    }
+
+    fn set_filter_period(&self, new_period_ms: u32) -> Result<(), EspErr> {
+        set_filter_period(new_period_ms)
+    }
+
+    fn get_filter_period(&self) -> Result<u32, EspErr> {
+        safe::get_filter_period()
+    }
+
+    fn filter_stop(&self) -> Result<(), EspErr> {
+        filter_stop()
+    }
+
+    fn filter_delete(&self) -> Result<(), EspErr> {
+        filter_delete()
+    }
+
+    fn set_filter_read_cb(&self, filter_cb: FilterCbT) -> Result<(), EspErr> {
+        set_filter_read_cb(filter_cb)
+    }
+
+    fn set_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr> {
+        set_group_mask(set1_mask, set2_mask, en_mask)
+    }
+
+    fn get_group_mask(&self) -> Result<GroupMask, EspErr> {
+        safe::get_group_mask()
+    }
+
+    fn clear_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr> {
+        clear_group_mask(set1_mask, set2_mask, en_mask)
+    }
+
+    fn intr_enable(&self) -> Result<(), EspErr> {
+        intr_enable()
+    }
+
+    fn intr_disable(&self) -> Result<(), EspErr> {
+        intr_disable()
+    }
+
+    fn intr_clear(&self) -> Result<(), EspErr> {
+        intr_clear()
+    }
+
+    fn pad_io_init(&self, touch_num: TouchPadChannel) -> Result<(), EspErr> {
+        pad_io_init(touch_num)
+    }
+
+    fn set_cnt_mode(
+        &self,
+        touch_num: TouchPadChannel,
+        slope: TouchCountSlope,
+        opt: TouchTieOption,
+    ) -> Result<(), EspErr> {
+        set_cnt_mode(touch_num, slope, opt)
+    }
+
+    fn get_cnt_mode(&self, touch_num: TouchPadChannel) -> Result<CntMode, EspErr> {
+        safe::get_cnt_mode(touch_num)
+    }
+
+    fn get_wakeup_status(&self) -> Result<TouchPadChannel, EspErr> {
+        safe::get_wakeup_status()
+    }
+
+    fn set_fsm_mode(&self, mode: TouchFSMMode) -> Result<(), EspErr> {
+        set_fsm_mode(mode)
+    }
+
+    fn get_fsm_mode(&self) -> Result<TouchFSMMode, EspErr> {
+        safe::get_fsm_mode()
+    }
+
+    fn clear_status(&self) -> Result<(), EspErr> {
+        clear_status()
+    }
+
+    fn get_status(&self) -> ChannelSet {
+        get_status()
+    }
+
+    fn meas_is_done(&self) -> bool {
+        meas_is_done()
+    }
+
+    fn isr_register(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+        isr_register(isr_handler, arg)
+    }
+
+    fn isr_deregister(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+        isr_deregister(isr_handler, arg)
+    }
+
+    fn timestamp_ms(&self) -> u32 {
+        timestamp_ms()
+    }
+}
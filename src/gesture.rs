@@ -0,0 +1,408 @@
+use crate::channel_set::ChannelSet;
+use crate::driver::TouchDriver;
+use crate::hal::TouchHal;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Direction of a swipe. `x` grows to the right and `y` grows downwards, as on a screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Gestures recognized by a `GestureRecognizer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// A short touch without travel, not followed by another tap within `multi_tap_ms`. Like
+    /// `DoubleTap`, it is only reported once that window is over, by the next `update` or
+    /// `expire`.
+    Tap,
+    DoubleTap,
+    TripleTap,
+    /// The touch stayed in place for `hold_ms`. Emitted while the finger is still down.
+    Hold,
+    /// A quick move over at least `min_travel`, from press to release.
+    Swipe(SwipeDirection),
+    /// A move over at least `min_travel` after a `Hold`.
+    PressSlide(SwipeDirection),
+}
+
+/// Position of a pad in the layout of a `GestureRecognizer`, in any unit (e.g. pad pitches).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PadPosition {
+    pub channel: TouchPadChannel,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Tuning of a `GestureRecognizer`. Times are in milliseconds, distances in layout units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GestureConfig {
+    /// Pads taking part in gestures. Touches on other channels are ignored.
+    pub layout: Vec<PadPosition>,
+    /// Longest touch that counts as a tap.
+    pub tap_ms: u32,
+    /// Longest time between the release of a tap and the next press for them to count as a
+    /// double or triple tap, 0 disables multi taps.
+    pub multi_tap_ms: u32,
+    /// Time in place before `Hold`.
+    pub hold_ms: u32,
+    /// Longest touch that counts as a swipe.
+    pub swipe_ms: u32,
+    /// Distance a touch has to travel to be a swipe or a slide.
+    pub min_travel: f32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            layout: Vec::new(),
+            tap_ms: 200,
+            multi_tap_ms: 300,
+            hold_ms: 600,
+            swipe_ms: 500,
+            min_travel: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    start_ms: u32,
+    start: (f32, f32),
+    position: (f32, f32),
+    /// Largest distance from `start` during the touch.
+    travel: f32,
+    held: bool,
+    slid: bool,
+}
+
+/// Gesture engine on top of the active status of the pads of a layout. It is driven by
+/// `(timestamp, status)` pairs, the status being the active channels as returned by `get_status`,
+/// so its timing only depends on the timestamps it is given.
+///
+/// The touch position is the center of the touched pads, so a finger moving over the layout
+/// is followed from pad to pad.
+///
+/// A tap is reported once `multi_tap_ms` has passed without another one. When the statuses
+/// stop coming, call `expire` after `tap_deadline` so the pending taps are not held back.
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    contact: Option<Contact>,
+    /// Taps waiting to find out whether another tap follows.
+    taps: u8,
+    last_tap_ms: u32,
+    gestures: Vec<Gesture>,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        GestureRecognizer {
+            config,
+            contact: None,
+            taps: 0,
+            last_tap_ms: 0,
+            gestures: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Whether a pad of the layout is currently touched.
+    pub fn is_touched(&self) -> bool {
+        self.contact.is_some()
+    }
+
+    /// Center of the pads of the layout set in `status`, `None` when none is.
+    fn position(&self, status: ChannelSet) -> Option<(f32, f32)> {
+        let (count, x, y) = self
+            .config
+            .layout
+            .iter()
+            .filter(|pad| status.contains(pad.channel))
+            .fold((0, 0.0, 0.0), |(count, x, y), pad| {
+                (count + 1, x + pad.x, y + pad.y)
+            });
+        (count != 0).then(|| (x / count as f32, y / count as f32))
+    }
+
+    /// Feed the status taken at `timestamp_ms` and return the gestures it completed.
+    pub fn update(
+        &mut self,
+        timestamp_ms: u32,
+        status: ChannelSet,
+    ) -> std::vec::Drain<'_, Gesture> {
+        self.gestures.clear();
+
+        let position = self.position(status);
+        self.expire_taps(timestamp_ms);
+        match (self.contact.is_some(), position) {
+            (false, Some(position)) => self.press(timestamp_ms, position),
+            (true, Some(position)) => self.track(timestamp_ms, position),
+            (true, None) => self.release(timestamp_ms),
+            (false, None) => {}
+        }
+
+        self.gestures.drain(..)
+    }
+
+    /// Read and clear the active status of the driver and feed it.
+    pub fn poll<H: TouchHal>(
+        &mut self,
+        driver: &TouchDriver<H>,
+        timestamp_ms: u32,
+    ) -> Result<std::vec::Drain<'_, Gesture>, TouchError> {
+        let status = driver.get_status();
+        driver.clear_status()?;
+        Ok(self.update(timestamp_ms, status))
+    }
+
+    /// Report the taps whose `multi_tap_ms` window is over at `timestamp_ms`, without feeding a
+    /// status.
+    pub fn expire(&mut self, timestamp_ms: u32) -> std::vec::Drain<'_, Gesture> {
+        self.gestures.clear();
+        self.expire_taps(timestamp_ms);
+        self.gestures.drain(..)
+    }
+
+    /// End of the window of the pending taps, `None` without pending taps. `expire` or `update`
+    /// report them after that time.
+    pub fn tap_deadline(&self) -> Option<u32> {
+        (self.taps != 0 && self.contact.is_none())
+            .then(|| self.last_tap_ms.wrapping_add(self.config.multi_tap_ms))
+    }
+
+    fn expire_taps(&mut self, timestamp_ms: u32) {
+        if self.taps != 0
+            && self.contact.is_none()
+            && timestamp_ms.wrapping_sub(self.last_tap_ms) > self.config.multi_tap_ms
+        {
+            self.flush_taps();
+        }
+    }
+
+    fn flush_taps(&mut self) {
+        match self.taps {
+            1 => self.gestures.push(Gesture::Tap),
+            2 => self.gestures.push(Gesture::DoubleTap),
+            _ => {}
+        }
+        self.taps = 0;
+    }
+
+    fn press(&mut self, timestamp_ms: u32, position: (f32, f32)) {
+        self.contact = Some(Contact {
+            start_ms: timestamp_ms,
+            start: position,
+            position,
+            travel: 0.0,
+            held: false,
+            slid: false,
+        });
+    }
+
+    fn track(&mut self, timestamp_ms: u32, position: (f32, f32)) {
+        let Some(contact) = self.contact.as_mut() else {
+            return;
+        };
+        contact.position = position;
+        contact.travel = contact.travel.max(distance(contact.start, position));
+        if !contact.held {
+            if contact.travel < self.config.min_travel
+                && timestamp_ms.wrapping_sub(contact.start_ms) >= self.config.hold_ms
+            {
+                contact.held = true;
+                contact.start = position;
+                contact.travel = 0.0;
+                self.flush_taps();
+                self.gestures.push(Gesture::Hold);
+            }
+        } else if !contact.slid && contact.travel >= self.config.min_travel {
+            contact.slid = true;
+            self.gestures
+                .push(Gesture::PressSlide(direction(contact.start, position)));
+        }
+    }
+
+    fn release(&mut self, timestamp_ms: u32) {
+        let Some(contact) = self.contact.take() else {
+            return;
+        };
+        if contact.held {
+            return;
+        }
+        let duration_ms = timestamp_ms.wrapping_sub(contact.start_ms);
+        if contact.travel >= self.config.min_travel {
+            self.flush_taps();
+            if duration_ms <= self.config.swipe_ms
+                && distance(contact.start, contact.position) >= self.config.min_travel
+            {
+                self.gestures
+                    .push(Gesture::Swipe(direction(contact.start, contact.position)));
+            }
+        } else if duration_ms <= self.config.tap_ms {
+            self.taps += 1;
+            self.last_tap_ms = timestamp_ms;
+            if self.taps == 3 {
+                self.taps = 0;
+                self.gestures.push(Gesture::TripleTap);
+            } else if self.config.multi_tap_ms == 0 {
+                self.flush_taps();
+            }
+        } else {
+            self.flush_taps();
+        }
+    }
+}
+
+fn distance(from: (f32, f32), to: (f32, f32)) -> f32 {
+    (to.0 - from.0).hypot(to.1 - from.1)
+}
+
+/// Main direction of the move from `from` to `to`.
+fn direction(from: (f32, f32), to: (f32, f32)) -> SwipeDirection {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if dx.abs() >= dy.abs() {
+        if dx < 0.0 {
+            SwipeDirection::Left
+        } else {
+            SwipeDirection::Right
+        }
+    } else if dy < 0.0 {
+        SwipeDirection::Up
+    } else {
+        SwipeDirection::Down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Gesture::*;
+
+    /// 3x3 grid, channel `n` at column `n % 3` and row `n / 3`.
+    fn grid() -> GestureRecognizer {
+        let layout = TouchPadChannel::ALL[..9]
+            .iter()
+            .enumerate()
+            .map(|(index, &channel)| PadPosition {
+                channel,
+                x: (index % 3) as f32,
+                y: (index / 3) as f32,
+            })
+            .collect();
+        GestureRecognizer::new(GestureConfig {
+            layout,
+            ..GestureConfig::default()
+        })
+    }
+
+    /// Feed `(timestamp, touched pad)` pairs, `None` for no touch.
+    fn feed(
+        recognizer: &mut GestureRecognizer,
+        stream: &[(u32, Option<usize>)],
+    ) -> Vec<(u32, Gesture)> {
+        let mut gestures = Vec::new();
+        for &(timestamp_ms, pad) in stream {
+            let status = pad.map_or(ChannelSet::new(), |pad| {
+                ChannelSet::from(TouchPadChannel::ALL[pad])
+            });
+            gestures.extend(
+                recognizer
+                    .update(timestamp_ms, status)
+                    .map(|gesture| (timestamp_ms, gesture)),
+            );
+        }
+        gestures
+    }
+
+    fn taps(count: u32) -> Vec<(u32, Option<usize>)> {
+        (0..count)
+            .flat_map(|index| [(index * 200, Some(4)), (index * 200 + 100, None)])
+            .collect()
+    }
+
+    #[test]
+    fn single_double_and_triple_tap() {
+        let mut recognizer = grid();
+        let mut stream = taps(1);
+        stream.extend([(400, None), (401, None)]);
+        assert_eq!(feed(&mut recognizer, &stream), vec![(401, Tap)]);
+
+        let mut stream = taps(2);
+        stream.push((700, None));
+        let mut recognizer = grid();
+        assert_eq!(feed(&mut recognizer, &stream), vec![(700, DoubleTap)]);
+
+        let mut recognizer = grid();
+        assert_eq!(feed(&mut recognizer, &taps(3)), vec![(500, TripleTap)]);
+        assert_eq!(recognizer.tap_deadline(), None);
+    }
+
+    #[test]
+    fn pending_tap_expires_without_update() {
+        let mut recognizer = grid();
+        assert!(feed(&mut recognizer, &taps(1)).is_empty());
+        assert_eq!(recognizer.tap_deadline(), Some(400));
+        assert_eq!(recognizer.expire(400).count(), 0);
+        assert_eq!(recognizer.expire(401).collect::<Vec<_>>(), vec![Tap]);
+        assert_eq!(recognizer.tap_deadline(), None);
+    }
+
+    #[test]
+    fn tap_and_hold() {
+        let mut recognizer = grid();
+        let stream = [(0, Some(4)), (300, Some(4)), (600, Some(4)), (900, None)];
+        assert_eq!(feed(&mut recognizer, &stream), vec![(600, Hold)]);
+    }
+
+    #[test]
+    fn swipes() {
+        for (pads, direction) in [
+            ([3, 4, 5], SwipeDirection::Right),
+            ([5, 4, 3], SwipeDirection::Left),
+            ([7, 4, 1], SwipeDirection::Up),
+            ([1, 4, 7], SwipeDirection::Down),
+        ] {
+            let mut recognizer = grid();
+            let stream = [
+                (0, Some(pads[0])),
+                (100, Some(pads[1])),
+                (200, Some(pads[2])),
+                (300, None),
+            ];
+            assert_eq!(
+                feed(&mut recognizer, &stream),
+                vec![(300, Swipe(direction))]
+            );
+        }
+
+        // Too slow for a swipe.
+        let mut recognizer = grid();
+        let stream = [(0, Some(3)), (300, Some(5)), (600, None)];
+        assert!(feed(&mut recognizer, &stream).is_empty());
+    }
+
+    #[test]
+    fn press_slide() {
+        let mut recognizer = grid();
+        let stream = [
+            (0, Some(1)),
+            (600, Some(1)),
+            (700, Some(4)),
+            (800, Some(7)),
+            (900, Some(8)),
+            (1000, None),
+        ];
+        assert_eq!(
+            feed(&mut recognizer, &stream),
+            vec![(600, Hold), (800, PressSlide(SwipeDirection::Down))]
+        );
+    }
+}
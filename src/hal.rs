@@ -0,0 +1,211 @@
+use crate::channel_set::ChannelSet;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::os::raw::c_void;
+
+pub type FilterCbT = Option<unsafe extern "C" fn(raw_value: *mut u16, filtered_value: *mut u16)>;
+
+pub type IntrHandler = Option<unsafe extern "C" fn(arg: *mut c_void)>;
+
+/// Touch sensor reference voltages, as returned by `get_voltage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoltageConfig {
+    pub high: TouchHighVoltage,
+    pub low: TouchLowVoltage,
+    pub attenuation: TouchVoltageAttenuation,
+}
+
+/// Touch sensor measurement and sleep time, as returned by `get_meas_time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeasTime {
+    pub sleep_cycle: u16,
+    pub meas_cycle: u16,
+}
+
+/// Touch sensor group masks, as returned by `get_group_mask`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupMask {
+    pub set1_mask: ChannelSet,
+    pub set2_mask: ChannelSet,
+    pub en_mask: ChannelSet,
+}
+
+/// Touch sensor charge/discharge speed of a pad, as returned by `get_cnt_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CntMode {
+    pub slope: TouchCountSlope,
+    pub opt: TouchTieOption,
+}
+
+/// Low level touch sensor calls. Each method mirrors one `touch_pad_*` function of the IDF,
+/// so the driver and everything built on it can run on the real peripheral (`EspTouchHal`,
+/// behind the `esp-idf` feature) or on the host (`SimTouchHal`).
+pub trait TouchHal {
+    /// Initialize touch module.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_STATE the touch module is already initialized
+    /// * ESP_ERR_NO_MEM Touch pad init error
+    fn init(&self) -> Result<(), EspErr>;
+
+    /// Deinitialize touch module.
+    ///
+    /// # Error
+    ///
+    /// * ESP_FAIL Touch pad driver not initialized
+    fn deinit(&self) -> Result<(), EspErr>;
+
+    /// Trigger a touch sensor measurement, only support in SW mode of FSM.
+    fn sw_start(&self) -> Result<(), EspErr>;
+
+    /// Set touch sensor interrupt trigger source.
+    fn set_trigger_source(&self, src: TouchTriggerSource) -> Result<(), EspErr>;
+
+    /// Get touch sensor interrupt trigger source.
+    fn get_trigger_source(&self) -> Result<TouchTriggerSource, EspErr>;
+
+    /// Set touch sensor interrupt trigger mode.
+    fn set_trigger_mode(&self, mode: TouchTriggerMode) -> Result<(), EspErr>;
+
+    /// Get touch sensor interrupt trigger mode.
+    fn get_trigger_mode(&self) -> Result<TouchTriggerMode, EspErr>;
+
+    /// Set touch sensor interrupt threshold.
+    fn set_thresh(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr>;
+
+    /// Get touch sensor interrupt threshold.
+    fn get_thresh(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr>;
+
+    /// Set touch sensor measurement and sleep time.
+    fn set_meas_time(&self, sleep_cycle: u16, meas_cycle: u16) -> Result<(), EspErr>;
+
+    /// Get touch sensor measurement and sleep time.
+    fn get_meas_time(&self) -> Result<MeasTime, EspErr>;
+
+    /// Set the interval between two measurements.
+    fn set_measurement_interval(&self, interval_cycle: u16) -> Result<(), EspErr>;
+
+    /// Get the interval between two measurements.
+    fn get_measurement_interval(&self) -> Result<u16, EspErr>;
+
+    /// Set the clock cycles of each measurement.
+    fn set_measurement_clock_cycles(&self, clock_cycle: u16) -> Result<(), EspErr>;
+
+    /// Get the clock cycles of each measurement.
+    fn get_measurement_clock_cycles(&self) -> Result<u16, EspErr>;
+
+    /// Set touch sensor reference voltages.
+    fn set_voltage(
+        &self,
+        touch_high_voltage: TouchHighVoltage,
+        touch_low_voltage: TouchLowVoltage,
+        touch_voltage_attenuation: TouchVoltageAttenuation,
+    ) -> Result<(), EspErr>;
+
+    /// Get touch sensor reference voltages.
+    fn get_voltage(&self) -> Result<VoltageConfig, EspErr>;
+
+    /// Configure touch pad interrupt threshold.
+    fn config(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr>;
+
+    /// get touch sensor counter value.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_STATE This touch pad hardware connection is error, the value of "touch_value" is 0.
+    fn read(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr>;
+
+    /// get filtered touch sensor counter value by IIR filter.
+    fn read_filtered(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr>;
+
+    /// get raw data (touch sensor counter value) from IIR filter process.
+    fn read_raw_data(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr>;
+
+    /// start touch pad filter function.
+    fn filter_start(&self, filter_period_ms: u32) -> Result<(), EspErr>;
+
+    /// set touch pad filter calibration period, in ms.
+    fn set_filter_period(&self, new_period_ms: u32) -> Result<(), EspErr>;
+
+    /// get touch pad filter calibration period, in ms.
+    fn get_filter_period(&self) -> Result<u32, EspErr>;
+
+    /// stop touch pad filter function.
+    fn filter_stop(&self) -> Result<(), EspErr>;
+
+    /// delete touch pad filter driver and release the memory.
+    fn filter_delete(&self) -> Result<(), EspErr>;
+
+    /// Register the callback function that is called after each IIR filter calculation.
+    fn set_filter_read_cb(&self, filter_cb: FilterCbT) -> Result<(), EspErr>;
+
+    /// Set touch sensor group mask.
+    fn set_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr>;
+
+    /// Get touch sensor group mask.
+    fn get_group_mask(&self) -> Result<GroupMask, EspErr>;
+
+    /// Clear touch sensor group mask.
+    fn clear_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr>;
+
+    /// To enable touch pad interrupt.
+    fn intr_enable(&self) -> Result<(), EspErr>;
+
+    /// To disable touch pad interrupt.
+    fn intr_disable(&self) -> Result<(), EspErr>;
+
+    /// To clear touch pad interrupt.
+    fn intr_clear(&self) -> Result<(), EspErr>;
+
+    /// Initialize touch pad GPIO.
+    fn pad_io_init(&self, touch_num: TouchPadChannel) -> Result<(), EspErr>;
+
+    /// Set touch sensor charge/discharge speed for each pad.
+    fn set_cnt_mode(
+        &self,
+        touch_num: TouchPadChannel,
+        slope: TouchCountSlope,
+        opt: TouchTieOption,
+    ) -> Result<(), EspErr>;
+
+    /// Get touch sensor charge/discharge speed for each pad.
+    fn get_cnt_mode(&self, touch_num: TouchPadChannel) -> Result<CntMode, EspErr>;
+
+    /// Get the touch pad which caused wakeup from deep sleep.
+    fn get_wakeup_status(&self) -> Result<TouchPadChannel, EspErr>;
+
+    /// Set touch sensor FSM mode.
+    fn set_fsm_mode(&self, mode: TouchFSMMode) -> Result<(), EspErr>;
+
+    /// Get touch sensor FSM mode.
+    fn get_fsm_mode(&self) -> Result<TouchFSMMode, EspErr>;
+
+    /// To clear the touch sensor channel active status.
+    fn clear_status(&self) -> Result<(), EspErr>;
+
+    /// Get the touch sensor channel active status.
+    fn get_status(&self) -> ChannelSet;
+
+    /// Check touch sensor measurement status.
+    fn meas_is_done(&self) -> bool;
+
+    /// Register touch-pad ISR.
+    fn isr_register(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr>;
+
+    /// Deregister the handler previously registered using isr_register.
+    fn isr_deregister(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr>;
+
+    /// Milliseconds since boot, used to timestamp interrupts. It must be callable from an ISR.
+    fn timestamp_ms(&self) -> u32;
+}
@@ -0,0 +1,279 @@
+use crate::channel_set::ChannelSet;
+use crate::driver::TouchDriver;
+use crate::hal::TouchHal;
+use crate::touch_pad_error::*;
+use std::cell::UnsafeCell;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+/// Touch interrupt, as passed to a `TouchDriver::subscribe` callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchInterrupt {
+    /// Channels active when the interrupt fired.
+    pub status_mask: ChannelSet,
+    /// Time of the interrupt, in milliseconds since boot.
+    pub timestamp: u32,
+}
+
+type Callback = Box<dyn FnMut(TouchInterrupt) + Send>;
+
+/// One subscription. Its callback is only called by the ISR, which does not nest.
+struct Slot {
+    id: usize,
+    callback: UnsafeCell<Callback>,
+}
+
+/// Callbacks of the subscriptions, as seen by the ISR.
+type List = Vec<Arc<Slot>>;
+
+/// Callbacks of the subscriptions of one driver, all run by a single ISR.
+///
+/// The ISR never locks: a change builds a new list, swaps it in, and waits for the ISRs reading
+/// the old one to finish before freeing it. This holds even for an ISR still running on the
+/// other core after being deregistered.
+pub(crate) struct Subscribers {
+    registry: Mutex<Registry>,
+    /// Current list, null while there is no subscription.
+    list: AtomicPtr<List>,
+    /// Number of ISRs reading a list.
+    readers: AtomicUsize,
+}
+
+/// Serializes the changes to the list.
+#[derive(Default)]
+struct Registry {
+    next_id: usize,
+    /// Whether the ISR is registered.
+    registered: bool,
+}
+
+// The callbacks are `Send`, and only the ISR calls them.
+unsafe impl Send for Slot {}
+unsafe impl Sync for Slot {}
+
+impl Default for Subscribers {
+    fn default() -> Self {
+        Subscribers {
+            registry: Mutex::default(),
+            list: AtomicPtr::new(ptr::null_mut()),
+            readers: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Drop for Subscribers {
+    fn drop(&mut self) {
+        let list = *self.list.get_mut();
+        if !list.is_null() {
+            drop(unsafe { Box::from_raw(list) });
+        }
+    }
+}
+
+impl Subscribers {
+    fn lock(&self) -> MutexGuard<'_, Registry> {
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Copy of the current list, to be changed and passed to `replace`. Requires `registry`.
+    fn snapshot(&self, _registry: &Registry) -> List {
+        let list = self.list.load(Ordering::SeqCst);
+        if list.is_null() {
+            Vec::new()
+        } else {
+            unsafe { (*list).clone() }
+        }
+    }
+
+    /// Swap in `list`, then free the old one once no ISR reads it. Requires `registry`.
+    fn replace(&self, _registry: &Registry, list: List) {
+        let new = if list.is_empty() {
+            ptr::null_mut()
+        } else {
+            Box::into_raw(Box::new(list))
+        };
+        let old = self.list.swap(new, Ordering::SeqCst);
+        while self.readers.load(Ordering::SeqCst) != 0 {
+            thread::yield_now();
+        }
+        if !old.is_null() {
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+
+    /// Call every callback, from the ISR only.
+    fn dispatch(&self, interrupt: TouchInterrupt) {
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        let list = self.list.load(Ordering::SeqCst);
+        if !list.is_null() {
+            for slot in unsafe { &*list } {
+                unsafe { (*slot.callback.get())(interrupt) };
+            }
+        }
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Installed as the raw ISR, `arg` being the driver. The status is read and cleared once, then
+/// every callback gets the same interrupt.
+unsafe extern "C" fn trampoline<H: TouchHal + Sync>(arg: *mut c_void) {
+    let driver = &*(arg as *const TouchDriver<H>);
+    let interrupt = TouchInterrupt {
+        status_mask: driver.get_status(),
+        timestamp: driver.timestamp_ms(),
+    };
+    let _ = driver.clear_status();
+    let _ = driver.intr_clear();
+    driver.subscribers().dispatch(interrupt);
+}
+
+/// Closure registered with `TouchDriver::subscribe`. It borrows the driver so it can never
+/// outlive it, and the closure is removed when the guard is dropped.
+pub struct Subscription<'d, H: TouchHal + Sync> {
+    driver: &'d TouchDriver<H>,
+    id: usize,
+}
+
+impl<'d, H: TouchHal + Sync> Subscription<'d, H> {
+    pub(crate) fn new(driver: &'d TouchDriver<H>, callback: Callback) -> Result<Self, TouchError> {
+        let subscribers = driver.subscribers();
+        let mut registry = subscribers.lock();
+        let id = registry.next_id;
+        let old = subscribers.snapshot(&registry);
+        let mut list = old.clone();
+        list.push(Arc::new(Slot {
+            id,
+            callback: UnsafeCell::new(callback),
+        }));
+        subscribers.replace(&registry, list);
+        if !registry.registered {
+            let arg = driver as *const _ as *mut c_void;
+            if let Err(err) = driver.isr_register(Some(trampoline::<H>), arg) {
+                subscribers.replace(&registry, old);
+                return Err(err);
+            }
+            registry.registered = true;
+        }
+        registry.next_id += 1;
+        Ok(Subscription { driver, id })
+    }
+}
+
+impl<H: TouchHal + Sync> Drop for Subscription<'_, H> {
+    fn drop(&mut self) {
+        let subscribers = self.driver.subscribers();
+        let mut registry = subscribers.lock();
+        let mut list = subscribers.snapshot(&registry);
+        list.retain(|slot| slot.id != self.id);
+        let empty = list.is_empty();
+        subscribers.replace(&registry, list);
+        if empty && registry.registered {
+            let arg = self.driver as *const _ as *mut c_void;
+            if self
+                .driver
+                .isr_deregister(Some(trampoline::<H>), arg)
+                .is_ok()
+            {
+                registry.registered = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+    use crate::touch_pad_enum::*;
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn every_subscriber_gets_the_status() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num3).unwrap();
+        pad.configure(500).unwrap();
+        driver.intr_enable().unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let subscribe = |name| {
+            let seen = Arc::clone(&seen);
+            driver
+                .subscribe(move |interrupt| {
+                    seen.lock().unwrap().push((name, interrupt.status_mask))
+                })
+                .unwrap()
+        };
+        let first = subscribe("first");
+        let second = subscribe("second");
+        assert_eq!(sim.isr_count(), 1);
+
+        sim.set_raw(TouchPadChannel::Num3, 100);
+        sim.tick();
+        let touched = ChannelSet::from(TouchPadChannel::Num3);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![("first", touched), ("second", touched)]
+        );
+        assert!(driver.get_status().is_empty());
+
+        drop(first);
+        seen.lock().unwrap().clear();
+        sim.tick();
+        assert_eq!(*seen.lock().unwrap(), vec![("second", touched)]);
+
+        drop(second);
+        assert_eq!(sim.isr_count(), 0);
+    }
+
+    #[test]
+    fn subscribe_and_drop_while_interrupts_fire() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num3).unwrap();
+        pad.configure(500).unwrap();
+        driver.set_fsm_mode(TouchFSMMode::Timer).unwrap();
+        driver.intr_enable().unwrap();
+        sim.set_raw(TouchPadChannel::Num3, 100);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let keep = {
+            let calls = Arc::clone(&calls);
+            driver
+                .subscribe(move |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                })
+                .unwrap()
+        };
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    sim.tick();
+                }
+            });
+            // Go on until the ISR ran often enough to overlap the changes of the list.
+            let mut rounds = 0;
+            while rounds < 200 || calls.load(Ordering::Relaxed) < 200 {
+                let data = String::from("alive");
+                let subscription = driver
+                    .subscribe(move |_| assert_eq!(data, "alive"))
+                    .unwrap();
+                drop(subscription);
+                rounds += 1;
+            }
+            stop.store(true, Ordering::Relaxed);
+        });
+
+        let before = calls.load(Ordering::Relaxed);
+        sim.tick();
+        assert_eq!(calls.load(Ordering::Relaxed), before + 1);
+        drop(keep);
+        assert_eq!(sim.isr_count(), 0);
+    }
+}
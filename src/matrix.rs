@@ -0,0 +1,244 @@
+use crate::baseline::BaselineTracker;
+use crate::slider::{centroid, channel_deltas, check_channels, is_contiguous, scale};
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::EspErr;
+
+/// Phase of a finger in a `MatrixReport`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Down,
+    Move,
+    /// The finger was lifted, the report repeats its last coordinates.
+    Up,
+}
+
+/// Coordinate report of a `TouchMatrix`. Coordinates are in `0..=x_resolution` and
+/// `0..=y_resolution`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixReport {
+    pub phase: TouchPhase,
+    pub x: u16,
+    pub y: u16,
+    /// Number of row x column crossings covered by the finger.
+    pub area: u16,
+}
+
+/// Tuning of a `TouchMatrix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixConfig {
+    /// Electrodes along the X axis, in order from 0 to `x_resolution`. At least one, and no
+    /// channel is listed twice on either axis.
+    pub x_channels: Vec<TouchPadChannel>,
+    /// Electrodes along the Y axis, in order from 0 to `y_resolution`. At least one.
+    pub y_channels: Vec<TouchPadChannel>,
+    pub x_resolution: u16,
+    pub y_resolution: u16,
+    /// Delta (baseline - raw) from which an electrode counts as touched, must be positive.
+    pub touch_delta: i32,
+}
+
+/// Trackpad made of a matrix of X and Y electrodes. Each axis is interpolated like a
+/// `TouchSlider`, the finger being where the two axes cross.
+///
+/// The electrodes only tell which rows and which columns are touched, so two fingers give four
+/// possible crossings. Readings that do not point to a single crossing (separated groups of
+/// electrodes on one axis, or only one axis touched) are rejected as ambiguous and leave the
+/// matrix unchanged, instead of reporting a ghost position.
+#[derive(Debug, Clone)]
+pub struct TouchMatrix {
+    config: MatrixConfig,
+    last: Option<MatrixReport>,
+}
+
+impl TouchMatrix {
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG `touch_delta` is not positive, or an axis is empty, holds `Max`
+    ///   or a channel listed twice on either axis
+    pub fn new(config: MatrixConfig) -> Result<Self, EspErr> {
+        if config.touch_delta <= 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        check_channels(&config.x_channels)?;
+        check_channels(&config.y_channels)?;
+        check_channels(&[config.x_channels.as_slice(), &config.y_channels].concat())?;
+        Ok(TouchMatrix { config, last: None })
+    }
+
+    pub fn config(&self) -> &MatrixConfig {
+        &self.config
+    }
+
+    /// Last report while a finger is down, `None` when the matrix is not touched.
+    pub fn current(&self) -> Option<MatrixReport> {
+        self.last
+    }
+
+    /// Feed the deltas of every channel, indexed by `TouchPadChannel`, and return the report it
+    /// caused. A finger that stays still produces no report.
+    pub fn update(&mut self, deltas: &[i32; TouchPadChannel::ALL.len()]) -> Option<MatrixReport> {
+        let touch_delta = self.config.touch_delta;
+        let x_deltas = channel_deltas(&self.config.x_channels, deltas);
+        let y_deltas = channel_deltas(&self.config.y_channels, deltas);
+        if !is_contiguous(&x_deltas, touch_delta) || !is_contiguous(&y_deltas, touch_delta) {
+            return None;
+        }
+
+        let point = match (
+            centroid(&x_deltas, touch_delta),
+            centroid(&y_deltas, touch_delta),
+        ) {
+            (Some(x), Some(y)) => Some((x, y)),
+            (None, None) => None,
+            _ => return None,
+        };
+        let Some((x, y)) = point else {
+            let last = self.last.take()?;
+            return Some(MatrixReport {
+                phase: TouchPhase::Up,
+                ..last
+            });
+        };
+
+        let active = |deltas: &[i32]| deltas.iter().filter(|&&delta| delta >= touch_delta).count();
+        let report = MatrixReport {
+            phase: if self.last.is_some() {
+                TouchPhase::Move
+            } else {
+                TouchPhase::Down
+            },
+            x: scale(x, x_deltas.len(), self.config.x_resolution),
+            y: scale(y, y_deltas.len(), self.config.y_resolution),
+            area: (active(&x_deltas) * active(&y_deltas)) as u16,
+        };
+        let moved = self
+            .last
+            .is_none_or(|last| (last.x, last.y, last.area) != (report.x, report.y, report.area));
+        self.last = Some(report);
+        moved.then_some(report)
+    }
+
+    /// Feed the current deltas of a `BaselineTracker`.
+    pub fn update_from(&mut self, tracker: &BaselineTracker) -> Option<MatrixReport> {
+        self.update(&tracker.deltas())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(touch_delta: i32) -> MatrixConfig {
+        MatrixConfig {
+            x_channels: vec![TouchPadChannel::Num0, TouchPadChannel::Num1],
+            y_channels: vec![TouchPadChannel::Num2, TouchPadChannel::Num3],
+            x_resolution: 100,
+            y_resolution: 100,
+            touch_delta,
+        }
+    }
+
+    #[test]
+    fn touch_delta_must_be_positive() {
+        assert_eq!(
+            TouchMatrix::new(config(0)).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(TouchMatrix::new(config(-5)).is_err());
+
+        let mut matrix = TouchMatrix::new(config(1)).unwrap();
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[1] = 40;
+        deltas[2] = 40;
+        assert_eq!(
+            matrix.update(&deltas),
+            Some(MatrixReport {
+                phase: TouchPhase::Down,
+                x: 100,
+                y: 0,
+                area: 1,
+            })
+        );
+    }
+
+    /// 5x5 matrix, X on `Num0..=Num4` and Y on `Num5..=Num9`, 100 positions per electrode.
+    fn matrix() -> TouchMatrix {
+        TouchMatrix::new(MatrixConfig {
+            x_channels: TouchPadChannel::ALL[..5].to_vec(),
+            y_channels: TouchPadChannel::ALL[5..].to_vec(),
+            x_resolution: 400,
+            y_resolution: 400,
+            touch_delta: 10,
+        })
+        .unwrap()
+    }
+
+    fn deltas(x: [i32; 5], y: [i32; 5]) -> [i32; TouchPadChannel::ALL.len()] {
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[..5].copy_from_slice(&x);
+        deltas[5..].copy_from_slice(&y);
+        deltas
+    }
+
+    fn report(phase: TouchPhase, x: u16, y: u16, area: u16) -> Option<MatrixReport> {
+        Some(MatrixReport { phase, x, y, area })
+    }
+
+    #[test]
+    fn crossing_is_located() {
+        let mut matrix = matrix();
+        assert_eq!(
+            matrix.update(&deltas([0, 0, 100, 0, 0], [0, 0, 0, 100, 0])),
+            report(TouchPhase::Down, 200, 300, 1)
+        );
+        assert_eq!(
+            matrix.update(&deltas([0, 50, 50, 0, 0], [0, 0, 0, 100, 20])),
+            report(TouchPhase::Move, 150, 317, 4)
+        );
+        assert_eq!(
+            matrix.update(&deltas([0, 50, 50, 0, 0], [0, 0, 0, 100, 20])),
+            None
+        );
+        assert_eq!(
+            matrix.update(&deltas([0; 5], [0; 5])),
+            report(TouchPhase::Up, 150, 317, 4)
+        );
+        assert_eq!(matrix.current(), None);
+    }
+
+    #[test]
+    fn ghosts_are_suppressed() {
+        let mut matrix = matrix();
+        let down = matrix.update(&deltas([0, 0, 100, 0, 0], [0, 0, 100, 0, 0]));
+        // Two fingers: two groups of columns and of rows, four possible crossings.
+        assert_eq!(
+            matrix.update(&deltas([100, 0, 0, 100, 0], [0, 100, 0, 0, 100])),
+            None
+        );
+        // Separated groups on one axis only.
+        assert_eq!(
+            matrix.update(&deltas([100, 0, 100, 0, 0], [0, 0, 100, 0, 0])),
+            None
+        );
+        // A single axis touched.
+        assert_eq!(matrix.update(&deltas([0, 0, 100, 0, 0], [0; 5])), None);
+        assert_eq!(matrix.current(), down);
+    }
+
+    #[test]
+    fn channels_are_checked() {
+        use TouchPadChannel::*;
+        let config =
+            |x_channels: Vec<TouchPadChannel>, y_channels: Vec<TouchPadChannel>| MatrixConfig {
+                x_channels,
+                y_channels,
+                ..config(10)
+            };
+        assert!(TouchMatrix::new(config(vec![], vec![Num2])).is_err());
+        assert!(TouchMatrix::new(config(vec![Num0], vec![])).is_err());
+        assert!(TouchMatrix::new(config(vec![Num0, Num0], vec![Num2])).is_err());
+        assert!(TouchMatrix::new(config(vec![Num0, Num1], vec![Num1, Num2])).is_err());
+        assert!(TouchMatrix::new(config(vec![Num0, Max], vec![Num2])).is_err());
+        assert!(TouchMatrix::new(config(vec![Num0], vec![Num2])).is_ok());
+    }
+}
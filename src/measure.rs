@@ -0,0 +1,170 @@
+use crate::channel_set::ChannelSet;
+use crate::driver::TouchDriver;
+use crate::hal::TouchHal;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Result of `TouchDriver::measure_all`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Time of the measurement, in milliseconds since boot.
+    pub timestamp_ms: u32,
+    /// Active channels after the measurement.
+    pub status: ChannelSet,
+    /// Counter value of each channel, indexed by `TouchPadChannel`. `None` for the channels that
+    /// are not enabled or read 0 (broken connection).
+    pub values: [Option<u16>; TouchPadChannel::ALL.len()],
+}
+
+impl Snapshot {
+    pub fn value(&self, touch_num: TouchPadChannel) -> Option<u16> {
+        self.values.get(touch_num as usize).copied().flatten()
+    }
+}
+
+/// Background thread running `measure_all` at a fixed period, for SW FSM mode without a
+/// hardware timer. The thread is stopped and joined when the sampler is dropped.
+pub struct PeriodicSampler {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PeriodicSampler {
+    /// Start sampling every `period`, each measurement waiting at most `timeout`. `callback`
+    /// gets every snapshot or measurement error. When a measurement takes longer than the
+    /// period, the next one starts right away and the schedule restarts from there.
+    pub fn start<H, F>(
+        driver: Arc<TouchDriver<H>>,
+        period: Duration,
+        timeout: Duration,
+        mut callback: F,
+    ) -> Result<Self, TouchError>
+    where
+        H: TouchHal + Send + Sync + 'static,
+        F: FnMut(Result<Snapshot, TouchError>) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name("touch-sampler".into())
+            .spawn(move || {
+                let mut next = Instant::now();
+                while !thread_stop.load(Ordering::Acquire) {
+                    callback(driver.measure_all(timeout));
+                    next = (next + period).max(Instant::now());
+                    while !thread_stop.load(Ordering::Acquire) && Instant::now() < next {
+                        thread::park_timeout(next.saturating_duration_since(Instant::now()));
+                    }
+                }
+            })
+            .map_err(TouchError::Spawn)?;
+        Ok(PeriodicSampler {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for PeriodicSampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+    use std::sync::mpsc::{channel, RecvTimeoutError};
+
+    #[test]
+    fn measure_all_reads_the_enabled_channels() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pads = [TouchPadChannel::Num2, TouchPadChannel::Num5]
+            .map(|touch_num| driver.take_pad(touch_num).unwrap());
+        for pad in &pads {
+            pad.configure(500).unwrap();
+        }
+        driver.set_fsm_mode(TouchFSMMode::Timer).unwrap();
+        sim.set_raw(TouchPadChannel::Num2, 300);
+        sim.set_raw(TouchPadChannel::Num5, 900);
+        sim.advance_ms(42);
+
+        let snapshot = driver.measure_all(Duration::from_millis(10)).unwrap();
+        assert_eq!(driver.get_fsm_mode().unwrap(), TouchFSMMode::SW);
+        assert_eq!(snapshot.timestamp_ms, 42);
+        assert_eq!(snapshot.status, ChannelSet::from(TouchPadChannel::Num2));
+        assert_eq!(snapshot.value(TouchPadChannel::Num2), Some(300));
+        assert_eq!(snapshot.value(TouchPadChannel::Num5), Some(900));
+        assert_eq!(snapshot.value(TouchPadChannel::Num0), None);
+        assert_eq!(snapshot.value(TouchPadChannel::Max), None);
+    }
+
+    #[test]
+    fn stalled_measurement_times_out() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        sim.set_stalled(true);
+        let start = Instant::now();
+        let err = driver.measure_once(Duration::from_millis(20)).unwrap_err();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(err.esp_err(), EspErr::EspErrTimeout);
+        assert!(driver.measure_all(Duration::from_millis(1)).is_err());
+
+        sim.set_stalled(false);
+        assert!(driver.measure_once(Duration::from_millis(20)).is_ok());
+    }
+
+    #[test]
+    fn sampler_runs_until_dropped() {
+        let sim = SimTouchHal::new();
+        let driver = Arc::new(TouchDriver::new(sim.clone()).unwrap());
+        let pad = driver.take_pad(TouchPadChannel::Num3).unwrap();
+        pad.configure(500).unwrap();
+        drop(pad);
+        sim.set_raw(TouchPadChannel::Num3, 700);
+
+        let (sender, results) = channel();
+        let sampler = PeriodicSampler::start(
+            Arc::clone(&driver),
+            Duration::from_millis(2),
+            Duration::from_millis(5),
+            move |result| {
+                let _ = sender.send(result);
+            },
+        )
+        .unwrap();
+        for _ in 0..3 {
+            let snapshot = results
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap()
+                .unwrap();
+            assert_eq!(snapshot.value(TouchPadChannel::Num3), Some(700));
+        }
+        sim.set_stalled(true);
+        let timed_out = (0..10).any(|_| {
+            results
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap()
+                .is_err_and(|err| err.esp_err() == EspErr::EspErrTimeout)
+        });
+        assert!(timed_out);
+
+        // The thread is joined, so its callback and the sender are gone.
+        drop(sampler);
+        while results.try_recv().is_ok() {}
+        assert_eq!(
+            results.recv_timeout(Duration::from_millis(20)).err(),
+            Some(RecvTimeoutError::Disconnected)
+        );
+    }
+}
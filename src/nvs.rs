@@ -0,0 +1,95 @@
+use crate::storage::KvStorage;
+use crate::touch_pad_error::*;
+use esp_idf_svc::sys::*;
+use std::ffi::CString;
+use std::ptr;
+
+/// Maximum length of an NVS namespace or key, without the terminating NUL.
+const MAX_KEY_LEN: usize = 15;
+
+fn c_key(key: &str) -> Result<CString, EspErr> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(EspErr::EspErrInvalidArg);
+    }
+    CString::new(key).map_err(|_| EspErr::EspErrInvalidArg)
+}
+
+/// `KvStorage` in a namespace of the default NVS partition. The partition must have been
+/// initialized with `nvs_flash_init` (or by taking `EspDefaultNvsPartition`) before `open`.
+/// Every write is committed right away.
+pub struct NvsStorage {
+    handle: nvs_handle_t,
+}
+
+impl NvsStorage {
+    /// Open a namespace for reading and writing, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// * ESP_ERR_INVALID_ARG the namespace is empty, longer than 15 bytes or contains a NUL
+    /// * ESP_FAIL the NVS partition is corrupted or not initialized
+    pub fn open(namespace: &str) -> Result<Self, EspErr> {
+        let namespace = c_key(namespace)?;
+        let mut handle = 0;
+        unsafe {
+            EspErr::return_message(nvs_open(
+                namespace.as_ptr(),
+                nvs_open_mode_t_NVS_READWRITE,
+                &mut handle,
+            ))?
+        };
+        Ok(NvsStorage { handle })
+    }
+}
+
+impl KvStorage for NvsStorage {
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, EspErr> {
+        let key = c_key(key)?;
+        let mut len = 0;
+        let err = unsafe { nvs_get_blob(self.handle, key.as_ptr(), ptr::null_mut(), &mut len) };
+        if err == ESP_ERR_NVS_NOT_FOUND as esp_err_t {
+            return Ok(None);
+        }
+        EspErr::return_message(err)?;
+        let mut value = vec![0u8; len];
+        unsafe {
+            EspErr::return_message(nvs_get_blob(
+                self.handle,
+                key.as_ptr(),
+                value.as_mut_ptr().cast(),
+                &mut len,
+            ))?
+        };
+        value.truncate(len);
+        Ok(Some(value))
+    }
+
+    fn set_blob(&mut self, key: &str, value: &[u8]) -> Result<(), EspErr> {
+        let key = c_key(key)?;
+        unsafe {
+            EspErr::return_message(nvs_set_blob(
+                self.handle,
+                key.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+            ))?;
+            EspErr::return_message(nvs_commit(self.handle))
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), EspErr> {
+        let key = c_key(key)?;
+        let err = unsafe { nvs_erase_key(self.handle, key.as_ptr()) };
+        if err == ESP_ERR_NVS_NOT_FOUND as esp_err_t {
+            return Ok(());
+        }
+        EspErr::return_message(err)?;
+        unsafe { EspErr::return_message(nvs_commit(self.handle)) }
+    }
+}
+
+impl Drop for NvsStorage {
+    fn drop(&mut self) {
+        unsafe { nvs_close(self.handle) };
+    }
+}
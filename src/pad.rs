@@ -0,0 +1,107 @@
+use crate::driver::TouchDriver;
+use crate::hal::*;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Owned handle to one touch channel, handed out by `TouchDriver::take_pad` / `take_pads`.
+/// Only one handle per channel exists at a time, so a pad can only be configured by its owner.
+/// The channel is given back to the driver when the handle is dropped.
+pub struct TouchPad<'d, H: TouchHal> {
+    driver: &'d TouchDriver<H>,
+    channel: TouchPadChannel,
+}
+
+impl<'d, H: TouchHal> TouchPad<'d, H> {
+    pub(crate) fn new(driver: &'d TouchDriver<H>, channel: TouchPadChannel) -> Self {
+        TouchPad { driver, channel }
+    }
+
+    pub(crate) fn driver(&self) -> &'d TouchDriver<H> {
+        self.driver
+    }
+
+    /// The channel owned by this handle.
+    pub fn channel(&self) -> TouchPadChannel {
+        self.channel
+    }
+
+    /// Configure the pad interrupt threshold and enable the channel. Like the IDF, this resets
+    /// the charge/discharge speed of the pad to the default, call `set_cnt_mode` afterwards.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument wrong
+    pub fn configure(&self, threshold: u16) -> Result<(), TouchError> {
+        self.driver.config(self.channel, threshold)
+    }
+
+    /// Set the pad interrupt threshold.
+    pub fn set_thresh(&self, threshold: u16) -> Result<(), TouchError> {
+        self.driver.set_thresh(self.channel, threshold)
+    }
+
+    /// Get the pad interrupt threshold.
+    pub fn get_thresh(&self) -> Result<u16, TouchError> {
+        self.driver.get_thresh(self.channel)
+    }
+
+    /// get touch sensor counter value.
+    ///
+    /// # Errors
+    ///
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection This touch pad hardware connection is error, the value of "touch_value" is 0.
+    pub fn read(&self) -> Result<u16, TouchError> {
+        self.driver.read(self.channel)
+    }
+
+    /// get filtered touch sensor counter value by IIR filter.
+    ///
+    /// # Errors
+    ///
+    /// * FilterNotStarted the filter is not running
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection the touch pad hardware connection is error
+    pub fn read_filtered(&self) -> Result<u16, TouchError> {
+        self.driver.read_filtered(self.channel)
+    }
+
+    /// get raw data (touch sensor counter value) from IIR filter process.
+    ///
+    /// # Errors
+    ///
+    /// * ChannelNotEnabled the channel is not in the enable mask, the value is 0
+    /// * BadConnection This touch pad hardware connection is error, the value of "touch_value" is 0.
+    pub fn read_raw_data(&self) -> Result<u16, TouchError> {
+        self.driver.read_raw_data(self.channel)
+    }
+
+    /// Set the pad charge/discharge speed.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if argument is wrong
+    pub fn set_cnt_mode(
+        &self,
+        slope: TouchCountSlope,
+        opt: TouchTieOption,
+    ) -> Result<(), TouchError> {
+        self.driver.set_cnt_mode(self.channel, slope, opt)
+    }
+
+    /// Get the pad charge/discharge speed.
+    pub fn get_cnt_mode(&self) -> Result<CntMode, TouchError> {
+        self.driver.get_cnt_mode(self.channel)
+    }
+
+    /// Initialize the pad GPIO.
+    pub fn io_init(&self) -> Result<(), TouchError> {
+        self.driver.pad_io_init(self.channel)
+    }
+}
+
+impl<H: TouchHal> Drop for TouchPad<'_, H> {
+    fn drop(&mut self) {
+        self.driver.release_pad(self.channel);
+    }
+}
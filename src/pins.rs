@@ -0,0 +1,318 @@
+use crate::chip::Chip;
+use crate::touch_pad_enum::*;
+use std::fmt;
+
+/// How bad a `PinDiagnostic` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PinSeverity {
+    /// The pad works, but the board needs care, e.g. a strapping pin that must not be loaded
+    /// at reset.
+    Warning,
+    /// The pad cannot work or breaks the board.
+    Error,
+}
+
+/// Problem with the GPIO of a planned touch channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PinIssue {
+    /// The channel has no pad on the chip, or the GPIO does not exist there.
+    NotAvailable,
+    /// Strapping pin sampled at reset. The pad capacitance can change the boot mode or, for
+    /// GPIO12 on the ESP32, select 1.8V flash and stop the board from booting.
+    Strapping,
+    /// Pin wired to the SPI flash or PSRAM.
+    FlashReserved,
+    /// Input-only pin, it cannot charge the pad.
+    InputOnly,
+    /// Pin already used by another peripheral or by another channel of the plan.
+    AlreadyClaimed,
+}
+
+impl fmt::Display for PinIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PinIssue::NotAvailable => "not available on this chip",
+            PinIssue::Strapping => "strapping pin",
+            PinIssue::FlashReserved => "reserved for flash/PSRAM",
+            PinIssue::InputOnly => "input-only pin",
+            PinIssue::AlreadyClaimed => "already claimed",
+        })
+    }
+}
+
+/// One finding of `PinPlan::check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PinDiagnostic {
+    pub channel: TouchPadChannel,
+    /// GPIO of the channel, `GpioNum::GpioNumNc` for `PinIssue::NotAvailable`.
+    pub gpio: GpioNum,
+    pub issue: PinIssue,
+    pub severity: PinSeverity,
+}
+
+impl PinDiagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == PinSeverity::Error
+    }
+}
+
+impl fmt::Display for PinDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            PinSeverity::Warning => "warning",
+            PinSeverity::Error => "error",
+        };
+        write!(f, "{}: {}", severity, self.channel)?;
+        if self.gpio != GpioNum::GpioNumNc {
+            write!(f, " (GPIO{})", self.gpio as i32)?;
+        }
+        write!(f, ": {}", self.issue)
+    }
+}
+
+/// Board around the touch channels, against which a planned channel set is checked before the
+/// pads are opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinPlan {
+    pub chip: Chip,
+    /// The module has PSRAM, which reserves more pins.
+    pub psram: bool,
+    /// GPIOs already used by other peripherals.
+    pub claimed: Vec<GpioNum>,
+}
+
+impl Default for PinPlan {
+    fn default() -> Self {
+        PinPlan::new(Chip::CURRENT)
+    }
+}
+
+impl PinPlan {
+    pub fn new(chip: Chip) -> Self {
+        PinPlan {
+            chip,
+            psram: false,
+            claimed: Vec::new(),
+        }
+    }
+
+    /// Mark a GPIO as used by another peripheral.
+    pub fn claim(&mut self, gpio: GpioNum) -> &mut Self {
+        self.claimed.push(gpio);
+        self
+    }
+
+    /// Check the GPIO of each planned channel, in order. A channel listed twice is reported
+    /// `AlreadyClaimed` the second time. An empty result means every pad is safe to use.
+    pub fn check(&self, channels: impl IntoIterator<Item = TouchPadChannel>) -> Vec<PinDiagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut claimed = self.claimed.clone();
+        for channel in channels {
+            let gpio = channel.gpio(self.chip);
+            let mut report = |issue, severity| {
+                diagnostics.push(PinDiagnostic {
+                    channel,
+                    gpio,
+                    issue,
+                    severity,
+                })
+            };
+            if gpio == GpioNum::GpioNumNc {
+                report(PinIssue::NotAvailable, PinSeverity::Error);
+                continue;
+            }
+            for (issue, severity) in self.issues(gpio, &claimed) {
+                report(issue, severity);
+            }
+            claimed.push(gpio);
+        }
+        diagnostics
+    }
+
+    /// Check a single GPIO against the same rules as `check`, e.g. one a pad is about to be
+    /// wired to. The touch GPIOs never sit on flash or input-only pins, other GPIOs can.
+    pub fn check_gpio(&self, gpio: GpioNum) -> Vec<(PinIssue, PinSeverity)> {
+        self.issues(gpio, &self.claimed)
+    }
+
+    fn issues(&self, gpio: GpioNum, claimed: &[GpioNum]) -> Vec<(PinIssue, PinSeverity)> {
+        if !gpio.exists(self.chip) {
+            return vec![(PinIssue::NotAvailable, PinSeverity::Error)];
+        }
+        let mut issues = Vec::new();
+        let num = gpio as i32;
+        if let Some(severity) = self.strapping(num) {
+            issues.push((PinIssue::Strapping, severity));
+        }
+        if self.reserved(num) {
+            issues.push((PinIssue::FlashReserved, PinSeverity::Error));
+        }
+        if self.input_only(num) {
+            issues.push((PinIssue::InputOnly, PinSeverity::Error));
+        }
+        if claimed.contains(&gpio) {
+            issues.push((PinIssue::AlreadyClaimed, PinSeverity::Error));
+        }
+        issues
+    }
+
+    /// Severity of a strapping pin, `None` for other pins.
+    fn strapping(&self, gpio: i32) -> Option<PinSeverity> {
+        match (self.chip, gpio) {
+            // MTDI selects the flash voltage, pulled high it boots 3.3V flash at 1.8V.
+            (Chip::Esp32, 12) => Some(PinSeverity::Error),
+            (Chip::Esp32, 0 | 2 | 5 | 15)
+            | (Chip::Esp32S2, 0 | 45 | 46)
+            | (Chip::Esp32S3, 0 | 3 | 45 | 46) => Some(PinSeverity::Warning),
+            _ => None,
+        }
+    }
+
+    fn reserved(&self, gpio: i32) -> bool {
+        match self.chip {
+            Chip::Esp32 => (6..=11).contains(&gpio) || (self.psram && (16..=17).contains(&gpio)),
+            Chip::Esp32S2 => (27..=32).contains(&gpio) || (self.psram && gpio == 26),
+            // Octal PSRAM also takes GPIO33 to GPIO37.
+            Chip::Esp32S3 => {
+                (27..=32).contains(&gpio)
+                    || (self.psram && (gpio == 26 || (33..=37).contains(&gpio)))
+            }
+        }
+    }
+
+    fn input_only(&self, gpio: i32) -> bool {
+        match self.chip {
+            Chip::Esp32 => (34..=39).contains(&gpio),
+            Chip::Esp32S2 => gpio == 46,
+            Chip::Esp32S3 => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(diagnostics: &[PinDiagnostic]) -> Vec<(TouchPadChannel, PinIssue, PinSeverity)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.channel, diagnostic.issue, diagnostic.severity))
+            .collect()
+    }
+
+    #[test]
+    fn esp32_strapping_pins() {
+        let plan = PinPlan::new(Chip::Esp32);
+        let diagnostics = plan.check([
+            TouchPadChannel::Num5,
+            TouchPadChannel::Num1,
+            TouchPadChannel::Num2,
+            TouchPadChannel::Num3,
+            TouchPadChannel::Num4,
+        ]);
+        assert_eq!(
+            issues(&diagnostics),
+            vec![
+                (
+                    TouchPadChannel::Num5,
+                    PinIssue::Strapping,
+                    PinSeverity::Error
+                ),
+                (
+                    TouchPadChannel::Num1,
+                    PinIssue::Strapping,
+                    PinSeverity::Warning
+                ),
+                (
+                    TouchPadChannel::Num2,
+                    PinIssue::Strapping,
+                    PinSeverity::Warning
+                ),
+                (
+                    TouchPadChannel::Num3,
+                    PinIssue::Strapping,
+                    PinSeverity::Warning
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].gpio, GpioNum::GpioNum12);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error: channel 5 (GPIO12): strapping pin"
+        );
+    }
+
+    #[test]
+    fn claimed_and_unavailable() {
+        let mut plan = PinPlan::new(Chip::Esp32S2);
+        plan.claim(GpioNum::GpioNum4);
+        let diagnostics = plan.check([
+            TouchPadChannel::Num4,
+            TouchPadChannel::Num0,
+            TouchPadChannel::Num6,
+            TouchPadChannel::Num6,
+        ]);
+        assert_eq!(
+            issues(&diagnostics),
+            vec![
+                (
+                    TouchPadChannel::Num4,
+                    PinIssue::AlreadyClaimed,
+                    PinSeverity::Error
+                ),
+                (
+                    TouchPadChannel::Num0,
+                    PinIssue::NotAvailable,
+                    PinSeverity::Error
+                ),
+                (
+                    TouchPadChannel::Num6,
+                    PinIssue::AlreadyClaimed,
+                    PinSeverity::Error
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[1].gpio, GpioNum::GpioNumNc);
+        assert!(plan.check([TouchPadChannel::Num7]).is_empty());
+    }
+
+    #[test]
+    fn flash_and_input_only_gpios() {
+        let mut plan = PinPlan::new(Chip::Esp32);
+        let error = |issue| vec![(issue, PinSeverity::Error)];
+        assert_eq!(
+            plan.check_gpio(GpioNum::GpioNum6),
+            error(PinIssue::FlashReserved)
+        );
+        assert_eq!(
+            plan.check_gpio(GpioNum::GpioNum34),
+            error(PinIssue::InputOnly)
+        );
+        assert_eq!(
+            plan.check_gpio(GpioNum::GpioNum24),
+            error(PinIssue::NotAvailable)
+        );
+        assert!(plan.check_gpio(GpioNum::GpioNum16).is_empty());
+        plan.psram = true;
+        assert_eq!(
+            plan.check_gpio(GpioNum::GpioNum16),
+            error(PinIssue::FlashReserved)
+        );
+
+        let mut plan = PinPlan::new(Chip::Esp32S3);
+        assert!(plan.check_gpio(GpioNum::GpioNum35).is_empty());
+        plan.psram = true;
+        assert_eq!(
+            plan.check_gpio(GpioNum::GpioNum35),
+            error(PinIssue::FlashReserved)
+        );
+        assert_eq!(
+            PinPlan::new(Chip::Esp32S2).check_gpio(GpioNum::GpioNum46),
+            vec![
+                (PinIssue::Strapping, PinSeverity::Warning),
+                (PinIssue::InputOnly, PinSeverity::Error),
+            ]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::cell::UnsafeCell;
+use std::hint;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Ring buffer shared by a `Producer` and a `Consumer`. `head` is only written by the consumer
+/// and `tail` only by the producer, both count every item ever read or written.
+struct Ring<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    overflows: AtomicU32,
+}
+
+// The producer and the consumer never touch the same slot at the same time.
+unsafe impl<T: Send, const N: usize> Sync for Ring<T, N> {}
+
+impl<T, const N: usize> Drop for Ring<T, N> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { self.slots[head % N].get_mut().assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+/// Create a bounded lock-free single-producer/single-consumer queue of `N` items.
+///
+/// The producer never blocks, locks or allocates, so it can push from an ISR, e.g. from a
+/// `TouchDriver::subscribe` callback, while a task drains the consumer.
+pub fn queue<T: Send, const N: usize>() -> (Producer<T, N>, Consumer<T, N>) {
+    const { assert!(N > 0, "the queue needs room for at least one item") };
+    let ring = Arc::new(Ring {
+        slots: std::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        overflows: AtomicU32::new(0),
+    });
+    (
+        Producer {
+            ring: Arc::clone(&ring),
+        },
+        Consumer { ring },
+    )
+}
+
+/// Sending side of a `queue`.
+pub struct Producer<T, const N: usize> {
+    ring: Arc<Ring<T, N>>,
+}
+
+impl<T: Send, const N: usize> Producer<T, N> {
+    /// Push an item. When the queue is full the item is dropped, counted as an overflow and
+    /// handed back.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(ring.head.load(Ordering::Acquire)) >= N {
+            ring.overflows.fetch_add(1, Ordering::Relaxed);
+            return Err(value);
+        }
+        unsafe { (*ring.slots[tail % N].get()).write(value) };
+        ring.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Number of items dropped because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+}
+
+/// Receiving side of a `queue`.
+pub struct Consumer<T, const N: usize> {
+    ring: Arc<Ring<T, N>>,
+}
+
+impl<T: Send, const N: usize> Consumer<T, N> {
+    /// Pop the oldest item, `None` when the queue is empty.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        if head == ring.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let value = unsafe { (*ring.slots[head % N].get()).assume_init_read() };
+        ring.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Wait for the next item. The producer cannot signal from an ISR, so this polls with a
+    /// backoff from spinning to sleeping 1 ms.
+    pub fn recv(&mut self) -> T {
+        let mut backoff = Backoff::default();
+        loop {
+            if let Some(value) = self.try_recv() {
+                return value;
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Wait for the next item for at most `timeout`, `None` when none arrived.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<T> {
+        let start = Instant::now();
+        let mut backoff = Backoff::default();
+        loop {
+            if let Some(value) = self.try_recv() {
+                return Some(value);
+            }
+            if start.elapsed() >= timeout {
+                return None;
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Number of items waiting in the queue.
+    pub fn len(&self) -> usize {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        self.ring.tail.load(Ordering::Acquire).wrapping_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of items dropped because the queue was full.
+    pub fn overflows(&self) -> u32 {
+        self.ring.overflows.load(Ordering::Relaxed)
+    }
+
+    /// Return the overflow count and reset it to 0.
+    pub fn take_overflows(&self) -> u32 {
+        self.ring.overflows.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Polling delay growing from spinning to yielding to sleeping.
+#[derive(Default)]
+pub(crate) struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    const SPIN_LIMIT: u32 = 6;
+    const YIELD_LIMIT: u32 = 10;
+
+    pub(crate) fn wait(&mut self) {
+        if self.step < Self::SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                hint::spin_loop();
+            }
+        } else if self.step < Self::YIELD_LIMIT {
+            thread::yield_now();
+        } else {
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.step = self.step.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threaded_producer_and_consumer() {
+        let (mut producer, mut consumer) = queue::<u32, 8>();
+        let sender = thread::spawn(move || {
+            for value in 0..10_000 {
+                let mut value = value;
+                while let Err(rejected) = producer.push(value) {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+            producer.overflows()
+        });
+        for expected in 0..10_000 {
+            assert_eq!(consumer.recv(), expected);
+        }
+        let overflows = sender.join().unwrap();
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.take_overflows(), overflows);
+    }
+
+    #[test]
+    fn overflows_are_counted() {
+        let (mut producer, mut consumer) = queue::<u32, 2>();
+        assert_eq!(producer.push(1), Ok(()));
+        assert_eq!(producer.push(2), Ok(()));
+        assert_eq!(producer.push(3), Err(3));
+        assert_eq!(producer.push(4), Err(4));
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.overflows(), 2);
+
+        assert_eq!(consumer.take_overflows(), 2);
+        assert_eq!(consumer.take_overflows(), 0);
+        assert_eq!(producer.overflows(), 0);
+        assert_eq!(consumer.try_recv(), Some(1));
+        assert_eq!(producer.push(5), Ok(()));
+        assert_eq!(consumer.try_recv(), Some(2));
+        assert_eq!(consumer.try_recv(), Some(5));
+        assert_eq!(consumer.try_recv(), None);
+    }
+
+    #[test]
+    fn recv_timeout_expires() {
+        let (_producer, mut consumer) = queue::<u32, 4>();
+        let start = Instant::now();
+        assert_eq!(consumer.recv_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let (mut producer, mut consumer) = queue::<u32, 4>();
+        producer.push(7).unwrap();
+        assert_eq!(consumer.recv_timeout(Duration::ZERO), Some(7));
+    }
+
+    #[test]
+    fn dropping_the_ring_drops_the_items() {
+        let item = Arc::new(());
+        let (mut producer, mut consumer) = queue::<Arc<()>, 4>();
+        for _ in 0..3 {
+            producer.push(Arc::clone(&item)).unwrap();
+        }
+        drop(consumer.try_recv());
+        assert_eq!(Arc::strong_count(&item), 3);
+
+        drop(producer);
+        assert_eq!(Arc::strong_count(&item), 3);
+        drop(consumer);
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+}
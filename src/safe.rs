@@ -0,0 +1,216 @@
+use crate::channel_set::ChannelSet;
+use crate::hal::{CntMode, GroupMask, MeasTime, VoltageConfig};
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use esp_idf_svc::sys::*;
+
+/// Get touch sensor interrupt trigger source.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown source
+pub(crate) fn get_trigger_source() -> Result<TouchTriggerSource, EspErr> {
+    let mut src = 0;
+    unsafe { EspErr::return_message(touch_pad_get_trigger_source(&mut src))? };
+    checked(TouchTriggerSource::from_raw(src))
+}
+
+/// Get touch sensor interrupt trigger mode.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown mode
+pub(crate) fn get_trigger_mode() -> Result<TouchTriggerMode, EspErr> {
+    let mut mode = 0;
+    unsafe { EspErr::return_message(touch_pad_get_trigger_mode(&mut mode))? };
+    checked(TouchTriggerMode::from_raw(mode))
+}
+
+/// Get touch sensor interrupt threshold.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+pub(crate) fn get_thresh(touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+    let mut threshold = 0;
+    unsafe { EspErr::return_message(touch_pad_get_thresh(touch_num as u32, &mut threshold))? };
+    Ok(threshold)
+}
+
+/// Get touch sensor measurement and sleep time.
+pub(crate) fn get_meas_time() -> Result<MeasTime, EspErr> {
+    let mut sleep_cycle = 0;
+    let mut meas_cycle = 0;
+    unsafe { EspErr::return_message(touch_pad_get_meas_time(&mut sleep_cycle, &mut meas_cycle))? };
+    Ok(MeasTime {
+        sleep_cycle,
+        meas_cycle,
+    })
+}
+
+/// Get the interval between two measurements.
+pub(crate) fn get_measurement_interval() -> Result<u16, EspErr> {
+    let mut interval_cycle = 0;
+    unsafe { EspErr::return_message(touch_pad_get_measurement_interval(&mut interval_cycle))? };
+    Ok(interval_cycle)
+}
+
+/// Get the clock cycles of each measurement.
+pub(crate) fn get_measurement_clock_cycles() -> Result<u16, EspErr> {
+    let mut clock_cycle = 0;
+    unsafe { EspErr::return_message(touch_pad_get_measurement_clock_cycles(&mut clock_cycle))? };
+    Ok(clock_cycle)
+}
+
+/// Get touch sensor reference voltage.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown voltage
+pub(crate) fn get_voltage() -> Result<VoltageConfig, EspErr> {
+    let mut high = 0;
+    let mut low = 0;
+    let mut attenuation = 0;
+    unsafe {
+        EspErr::return_message(touch_pad_get_voltage(&mut high, &mut low, &mut attenuation))?
+    };
+    Ok(VoltageConfig {
+        high: checked(TouchHighVoltage::from_raw(high))?,
+        low: checked(TouchLowVoltage::from_raw(low))?,
+        attenuation: checked(TouchVoltageAttenuation::from_raw(attenuation))?,
+    })
+}
+
+/// get touch sensor counter value.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG Touch pad parameter error
+/// * ESP_ERR_INVALID_STATE This touch pad hardware connection is error, the value of "touch_value" is 0.
+/// * ESP_FAIL Touch pad not initialized
+pub(crate) fn read(touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+    let mut touch_value = 0;
+    unsafe { EspErr::return_message(touch_pad_read(touch_num as u32, &mut touch_value))? };
+    Ok(touch_value)
+}
+
+/// get filtered touch sensor counter value by IIR filter.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG Touch pad parameter error
+/// * ESP_ERR_INVALID_STATE This touch pad hardware connection is error, the value of "touch_value" is 0.
+/// * ESP_FAIL Touch pad not initialized
+pub(crate) fn read_filtered(touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+    let mut touch_value = 0;
+    unsafe { EspErr::return_message(touch_pad_read_filtered(touch_num as u32, &mut touch_value))? };
+    Ok(touch_value)
+}
+
+/// get raw data (touch sensor counter value) from IIR filter process.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_ARG Touch pad parameter error
+/// * ESP_ERR_INVALID_STATE This touch pad hardware connection is error, the value of "touch_value" is 0.
+/// * ESP_FAIL Touch pad not initialized
+pub(crate) fn read_raw_data(touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+    let mut touch_value = 0;
+    unsafe { EspErr::return_message(touch_pad_read_raw_data(touch_num as u32, &mut touch_value))? };
+    Ok(touch_value)
+}
+
+/// Get touch sensor group mask.
+pub(crate) fn get_group_mask() -> Result<GroupMask, EspErr> {
+    let mut set1_mask = 0;
+    let mut set2_mask = 0;
+    let mut en_mask = 0;
+    unsafe {
+        EspErr::return_message(touch_pad_get_group_mask(
+            &mut set1_mask,
+            &mut set2_mask,
+            &mut en_mask,
+        ))?
+    };
+    let mask = |bits| ChannelSet::from_bits(bits).map_err(|_| EspErr::EspErrInvalidResponse);
+    Ok(GroupMask {
+        set1_mask: mask(set1_mask)?,
+        set2_mask: mask(set2_mask)?,
+        en_mask: mask(en_mask)?,
+    })
+}
+
+/// get touch pad filter calibration period, in ms. Need to call filter_start before all touch filter APIs
+///
+/// # Errors
+///
+/// * ESP_ERR_INVALID_STATE driver state error
+pub(crate) fn get_filter_period() -> Result<u32, EspErr> {
+    let mut period_ms = 0;
+    unsafe { EspErr::return_message(touch_pad_get_filter_period(&mut period_ms))? };
+    Ok(period_ms)
+}
+
+/// Get touch sensor charge/discharge speed for each pad.
+///
+/// # Arguments
+///
+/// * `touch_num` - TouchPadChannel.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_ARG if argument is wrong
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown slope or tie option
+pub(crate) fn get_cnt_mode(touch_num: TouchPadChannel) -> Result<CntMode, EspErr> {
+    let mut slope = 0;
+    let mut opt = 0;
+    unsafe {
+        EspErr::return_message(touch_pad_get_cnt_mode(
+            touch_num as u32,
+            &mut slope,
+            &mut opt,
+        ))?
+    };
+    Ok(CntMode {
+        slope: checked(TouchCountSlope::from_raw(slope))?,
+        opt: checked(TouchTieOption::from_raw(opt))?,
+    })
+}
+
+/// Get the touch pad which caused wakeup from deep sleep.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown channel
+pub(crate) fn get_wakeup_status() -> Result<TouchPadChannel, EspErr> {
+    let mut pad_num = 0;
+    unsafe { EspErr::return_message(touch_pad_get_wakeup_status(&mut pad_num))? };
+    checked(TouchPadChannel::from_raw(pad_num))
+}
+
+/// Get touch sensor FSM mode.
+///
+/// # Error
+///
+/// * ESP_ERR_INVALID_RESPONSE if the IDF returned an unknown mode
+pub(crate) fn get_fsm_mode() -> Result<TouchFSMMode, EspErr> {
+    let mut mode = 0;
+    unsafe { EspErr::return_message(touch_pad_get_fsm_mode(&mut mode))? };
+    checked(TouchFSMMode::from_raw(mode))
+}
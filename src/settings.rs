@@ -0,0 +1,214 @@
+use crate::channel_set::ChannelSet;
+use crate::config::ConfigMismatch;
+use crate::driver::{TouchDriver, TouchFilter};
+use crate::hal::{CntMode, GroupMask, MeasTime, TouchHal, VoltageConfig};
+use crate::pad::TouchPad;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+
+/// Snapshot of every touch sensor setting the driver can read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchSettings {
+    /// Interrupt threshold of every channel, indexed by `TouchPadChannel`.
+    pub thresholds: [u16; TouchPadChannel::ALL.len()],
+    /// Charge/discharge speed of every channel, indexed by `TouchPadChannel`.
+    pub cnt_modes: [CntMode; TouchPadChannel::ALL.len()],
+    /// Measurement interval and clock cycles of each measurement.
+    pub meas_time: MeasTime,
+    pub voltage: VoltageConfig,
+    pub fsm_mode: TouchFSMMode,
+    pub trigger_mode: TouchTriggerMode,
+    pub trigger_source: TouchTriggerSource,
+    pub group_mask: GroupMask,
+    /// Filter calibration period, `None` when the filter is not running.
+    pub filter_period_ms: Option<u32>,
+}
+
+impl TouchSettings {
+    /// Read every setting through the getters.
+    pub fn capture<H: TouchHal>(driver: &TouchDriver<H>) -> Result<Self, TouchError> {
+        let mut thresholds = [0; TouchPadChannel::ALL.len()];
+        let mut cnt_modes = [CntMode {
+            slope: TouchCountSlope::Slope0,
+            opt: TouchTieOption::Low,
+        }; TouchPadChannel::ALL.len()];
+        for touch_num in TouchPadChannel::ALL {
+            thresholds[touch_num as usize] = driver.get_thresh(touch_num)?;
+            cnt_modes[touch_num as usize] = driver.get_cnt_mode(touch_num)?;
+        }
+        Ok(TouchSettings {
+            thresholds,
+            cnt_modes,
+            meas_time: driver.get_meas_time()?,
+            voltage: driver.get_voltage()?,
+            fsm_mode: driver.get_fsm_mode()?,
+            trigger_mode: driver.get_trigger_mode()?,
+            trigger_source: driver.get_trigger_source()?,
+            group_mask: driver.get_group_mask()?,
+            filter_period_ms: driver.filter_period()?,
+        })
+    }
+
+    /// Apply the captured settings again, e.g. after the driver was dropped and created again.
+    ///
+    /// The threshold and charge/discharge speed are restored on the channels of `pads` and on
+    /// the channels no `TouchPad` is held for, the enabled ones getting their GPIO initialized
+    /// again. If the filter was running it is started again and its guard returned, or only its
+    /// period is set if it is still running. A running filter is not stopped when it was not
+    /// running at capture time, its guard has to be dropped for that.
+    ///
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG if a setting is wrong
+    pub fn restore<'d, H: TouchHal>(
+        &self,
+        driver: &'d TouchDriver<H>,
+        pads: &[TouchPad<'d, H>],
+    ) -> Result<Option<TouchFilter<'d, H>>, TouchError> {
+        driver.set_fsm_mode(self.fsm_mode)?;
+        driver.set_voltage(
+            self.voltage.high,
+            self.voltage.low,
+            self.voltage.attenuation,
+        )?;
+        driver.set_meas_time(self.meas_time.sleep_cycle, self.meas_time.meas_cycle)?;
+        for touch_num in TouchPadChannel::ALL {
+            let owned = pads.iter().any(|pad| pad.channel() == touch_num);
+            if !owned && driver.is_pad_taken(touch_num) {
+                continue;
+            }
+            let index = touch_num as usize;
+            if self.group_mask.en_mask.contains(touch_num) {
+                driver.pad_io_init(touch_num)?;
+            }
+            let cnt_mode = self.cnt_modes[index];
+            driver.set_cnt_mode(touch_num, cnt_mode.slope, cnt_mode.opt)?;
+            driver.set_thresh(touch_num, self.thresholds[index])?;
+        }
+        driver.set_trigger_mode(self.trigger_mode)?;
+        driver.set_trigger_source(self.trigger_source)?;
+        driver.clear_group_mask(ChannelSet::ALL, ChannelSet::ALL, ChannelSet::ALL)?;
+        driver.set_group_mask(
+            self.group_mask.set1_mask,
+            self.group_mask.set2_mask,
+            self.group_mask.en_mask,
+        )?;
+        let Some(period_ms) = self.filter_period_ms else {
+            return Ok(None);
+        };
+        if driver.set_running_filter_period(period_ms)? {
+            return Ok(None);
+        }
+        driver.filter_start(period_ms).map(Some)
+    }
+
+    /// Settings that differ in `other`, `self` being the expected values.
+    pub fn diff(&self, other: &TouchSettings) -> Vec<ConfigMismatch> {
+        let mut mismatches = Vec::new();
+        for touch_num in TouchPadChannel::ALL {
+            let index = touch_num as usize;
+            if self.cnt_modes[index] != other.cnt_modes[index] {
+                mismatches.push(ConfigMismatch::CntMode {
+                    channel: touch_num,
+                    expected: self.cnt_modes[index],
+                    actual: other.cnt_modes[index],
+                });
+            }
+            if self.thresholds[index] != other.thresholds[index] {
+                mismatches.push(ConfigMismatch::Threshold {
+                    channel: touch_num,
+                    expected: self.thresholds[index],
+                    actual: other.thresholds[index],
+                });
+            }
+        }
+        let mut check = |mismatch: Option<ConfigMismatch>| mismatches.extend(mismatch);
+        check(
+            (self.meas_time != other.meas_time).then_some(ConfigMismatch::MeasTime {
+                expected: self.meas_time,
+                actual: other.meas_time,
+            }),
+        );
+        check(
+            (self.voltage != other.voltage).then_some(ConfigMismatch::Voltage {
+                expected: self.voltage,
+                actual: other.voltage,
+            }),
+        );
+        check(
+            (self.fsm_mode != other.fsm_mode).then_some(ConfigMismatch::FsmMode {
+                expected: self.fsm_mode,
+                actual: other.fsm_mode,
+            }),
+        );
+        check(
+            (self.trigger_mode != other.trigger_mode).then_some(ConfigMismatch::TriggerMode {
+                expected: self.trigger_mode,
+                actual: other.trigger_mode,
+            }),
+        );
+        check((self.trigger_source != other.trigger_source).then_some(
+            ConfigMismatch::TriggerSource {
+                expected: self.trigger_source,
+                actual: other.trigger_source,
+            },
+        ));
+        check(
+            (self.group_mask != other.group_mask).then_some(ConfigMismatch::GroupMask {
+                expected: self.group_mask,
+                actual: other.group_mask,
+            }),
+        );
+        check((self.filter_period_ms != other.filter_period_ms).then_some(
+            ConfigMismatch::FilterPeriod {
+                expected: self.filter_period_ms,
+                actual: other.filter_period_ms,
+            },
+        ));
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimTouchHal;
+
+    #[test]
+    fn restore_after_reinit() {
+        let sim = SimTouchHal::new();
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let pad = driver.take_pad(TouchPadChannel::Num6).unwrap();
+        pad.configure(600).unwrap();
+        pad.set_cnt_mode(TouchCountSlope::Slope4, TouchTieOption::High)
+            .unwrap();
+        driver.set_meas_time(0x200, 0x300).unwrap();
+        let filter = driver.filter_start(20).unwrap();
+        let captured = TouchSettings::capture(&driver).unwrap();
+        drop(filter);
+        drop(pad);
+        drop(driver);
+
+        let driver = TouchDriver::new(sim.clone()).unwrap();
+        let fresh = TouchSettings::capture(&driver).unwrap();
+        let mismatches = captured.diff(&fresh);
+        let meas_time = mismatches
+            .iter()
+            .filter(|mismatch| matches!(mismatch, ConfigMismatch::MeasTime { .. }))
+            .count();
+        assert_eq!(meas_time, 1);
+        assert!(mismatches.contains(&ConfigMismatch::FilterPeriod {
+            expected: Some(20),
+            actual: None,
+        }));
+
+        let filter = captured.restore(&driver, &[]).unwrap();
+        assert!(filter.is_some());
+        assert!(sim.is_io_initialized(TouchPadChannel::Num6));
+        let restored = TouchSettings::capture(&driver).unwrap();
+        assert_eq!(captured.diff(&restored), vec![]);
+
+        // The filter is still running, only its period is set.
+        assert!(captured.restore(&driver, &[]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,766 @@
+use crate::channel_set::ChannelSet;
+use crate::hal::*;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::*;
+use std::os::raw::c_void;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+const CHANNEL_COUNT: usize = TouchPadChannel::Max as usize;
+
+/// Counter value of an untouched simulated pad.
+pub const SIM_DEFAULT_RAW: u16 = 1000;
+
+/// `TOUCH_PAD_SLOPE_DEFAULT` / `TOUCH_PAD_TIE_OPT_DEFAULT` of the IDF.
+const SIM_DEFAULT_CNT_MODE: CntMode = CntMode {
+    slope: TouchCountSlope::Slope7,
+    opt: TouchTieOption::Low,
+};
+
+#[derive(Clone, Copy)]
+struct SimChannel {
+    raw: u16,
+    /// Amplitude of the square wave added to the reads.
+    noise: u16,
+    /// Whether the next read is above `raw`.
+    noise_high: bool,
+    filtered: u16,
+    thresh: u16,
+    cnt_mode: CntMode,
+    io_init: bool,
+}
+
+struct SimState {
+    initialized: bool,
+    channels: [SimChannel; CHANNEL_COUNT],
+    trigger_mode: TouchTriggerMode,
+    trigger_source: TouchTriggerSource,
+    meas_time: MeasTime,
+    voltage: VoltageConfig,
+    group_mask: GroupMask,
+    fsm_mode: TouchFSMMode,
+    intr_enabled: bool,
+    status: u32,
+    meas_done: bool,
+    filter_period_ms: Option<u32>,
+    filter_cb: FilterCbT,
+    isr_handlers: Vec<(unsafe extern "C" fn(arg: *mut c_void), usize)>,
+    wakeup_status: Option<TouchPadChannel>,
+    time_ms: u32,
+    /// Measurements started by `sw_start` never complete.
+    stalled: bool,
+}
+
+impl SimState {
+    fn new() -> Self {
+        SimState {
+            initialized: false,
+            channels: [SimChannel {
+                raw: SIM_DEFAULT_RAW,
+                noise: 0,
+                noise_high: true,
+                filtered: SIM_DEFAULT_RAW,
+                thresh: 0,
+                cnt_mode: SIM_DEFAULT_CNT_MODE,
+                io_init: false,
+            }; CHANNEL_COUNT],
+            trigger_mode: TouchTriggerMode::Below,
+            trigger_source: TouchTriggerSource::Set1,
+            meas_time: MeasTime {
+                sleep_cycle: 0x1000,
+                meas_cycle: 0x7fff,
+            },
+            voltage: VoltageConfig {
+                high: TouchHighVoltage::V2_7,
+                low: TouchLowVoltage::V0_5,
+                attenuation: TouchVoltageAttenuation::V0_5,
+            },
+            group_mask: GroupMask {
+                set1_mask: ChannelSet::new(),
+                set2_mask: ChannelSet::new(),
+                en_mask: ChannelSet::new(),
+            },
+            fsm_mode: TouchFSMMode::Timer,
+            intr_enabled: false,
+            status: 0,
+            meas_done: true,
+            filter_period_ms: None,
+            filter_cb: None,
+            isr_handlers: Vec::new(),
+            wakeup_status: None,
+            time_ms: 0,
+            stalled: false,
+        }
+    }
+
+    fn ready(&self) -> Result<(), EspErr> {
+        if self.initialized {
+            Ok(())
+        } else {
+            Err(EspErr::EspFail)
+        }
+    }
+
+    fn channel(&mut self, touch_num: TouchPadChannel) -> Result<&mut SimChannel, EspErr> {
+        self.ready()?;
+        self.channels
+            .get_mut(touch_num as usize)
+            .ok_or(EspErr::EspErrInvalidArg)
+    }
+
+    /// Channel whose counter is read. A channel out of the enable mask is not measured and reads
+    /// 0, which the IDF reports as ESP_ERR_INVALID_STATE.
+    fn measured(&mut self, touch_num: TouchPadChannel) -> Result<&mut SimChannel, EspErr> {
+        let enabled = self.group_mask.en_mask.contains(touch_num);
+        let channel = self.channel(touch_num)?;
+        if !enabled {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        Ok(channel)
+    }
+
+    fn is_active(&self, index: usize) -> bool {
+        let channel = &self.channels[index];
+        if !self
+            .group_mask
+            .en_mask
+            .contains(TouchPadChannel::ALL[index])
+            || channel.thresh == 0
+        {
+            return false;
+        }
+        match self.trigger_mode {
+            TouchTriggerMode::Above => channel.raw > channel.thresh,
+            _ => channel.raw < channel.thresh,
+        }
+    }
+
+    /// Run one measurement over every enabled channel. Returns the interrupt handlers and filter
+    /// callback to run once the state lock is released.
+    fn measure(&mut self) -> Pending {
+        let mut active = 0u32;
+        for index in 0..CHANNEL_COUNT {
+            if self.is_active(index) {
+                active |= 1 << index;
+            }
+        }
+        self.status |= active;
+        self.meas_done = true;
+
+        let mut pending = Pending::default();
+        if self.filter_period_ms.is_some() {
+            let en_mask = self.group_mask.en_mask;
+            for (index, channel) in self.channels.iter_mut().enumerate() {
+                channel.filtered = channel.raw;
+                if en_mask.contains(TouchPadChannel::ALL[index]) {
+                    pending.raw[index] = channel.raw;
+                    pending.filtered[index] = channel.raw;
+                }
+            }
+            pending.filter_cb = self.filter_cb;
+        }
+
+        let active = ChannelSet::from_bits_truncate(active);
+        let set1 = !(active & self.group_mask.set1_mask).is_empty();
+        let set2 = !(active & self.group_mask.set2_mask).is_empty();
+        let triggered = match self.trigger_source {
+            TouchTriggerSource::Both => set1 && set2,
+            _ => set1,
+        };
+        if self.intr_enabled && triggered {
+            pending.isr_handlers = self.isr_handlers.clone();
+        }
+        pending
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    raw: [u16; CHANNEL_COUNT],
+    filtered: [u16; CHANNEL_COUNT],
+    filter_cb: FilterCbT,
+    isr_handlers: Vec<(unsafe extern "C" fn(arg: *mut c_void), usize)>,
+}
+
+impl Pending {
+    /// Run the callbacks while holding `isr`, so the handlers of two measurements never overlap,
+    /// like an ISR that does not nest.
+    fn run(mut self, isr: &Mutex<()>) {
+        let _isr = isr.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(filter_cb) = self.filter_cb {
+            unsafe { filter_cb(self.raw.as_mut_ptr(), self.filtered.as_mut_ptr()) };
+        }
+        for (handler, arg) in self.isr_handlers {
+            unsafe { handler(arg as *mut c_void) };
+        }
+    }
+}
+
+/// In-process model of the touch peripheral with ten channels. Counter values are set from the
+/// outside with `set_raw`, and a measurement (`tick` in timer mode, `sw_start` in SW mode)
+/// updates the status mask, the IIR filter and fires the registered interrupt handlers, just like
+/// the hardware would. Channels out of the enable mask are not measured and read 0. Clones
+/// share the same state, so a test can keep one to drive the pads while the driver owns another.
+#[derive(Clone)]
+pub struct SimTouchHal {
+    state: Arc<Mutex<SimState>>,
+    isr: Arc<Mutex<()>>,
+}
+
+impl Default for SimTouchHal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimTouchHal {
+    pub fn new() -> Self {
+        SimTouchHal {
+            state: Arc::new(Mutex::new(SimState::new())),
+            isr: Arc::new(Mutex::new(())),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Set the counter value of a pad. A value of 0 models a broken connection.
+    pub fn set_raw(&self, touch_num: TouchPadChannel, value: u16) {
+        if let Some(channel) = self.state().channels.get_mut(touch_num as usize) {
+            channel.raw = value;
+        }
+    }
+
+    /// Add a square wave of `amplitude` to the reads of a pad: successive reads alternate
+    /// between `raw + amplitude` and `raw - amplitude`, for a mean of `raw` and a standard
+    /// deviation of `amplitude` over an even number of reads.
+    pub fn set_noise(&self, touch_num: TouchPadChannel, amplitude: u16) {
+        if let Some(channel) = self.state().channels.get_mut(touch_num as usize) {
+            channel.noise = amplitude;
+            channel.noise_high = true;
+        }
+    }
+
+    /// Current counter value of a pad.
+    pub fn raw(&self, touch_num: TouchPadChannel) -> u16 {
+        self.state()
+            .channels
+            .get(touch_num as usize)
+            .map_or(0, |channel| channel.raw)
+    }
+
+    /// Set the pad reported by `get_wakeup_status`.
+    pub fn set_wakeup_status(&self, touch_num: Option<TouchPadChannel>) {
+        self.state().wakeup_status = touch_num;
+    }
+
+    /// Make the measurements started by `sw_start` hang, as a faulty peripheral would, or work
+    /// again.
+    pub fn set_stalled(&self, stalled: bool) {
+        self.state().stalled = stalled;
+    }
+
+    /// Move the simulated clock of `timestamp_ms` forward.
+    pub fn advance_ms(&self, ms: u32) {
+        let mut state = self.state();
+        state.time_ms = state.time_ms.wrapping_add(ms);
+    }
+
+    /// Run one timer-driven measurement. Does nothing unless the driver is initialized and the
+    /// FSM is in timer mode.
+    pub fn tick(&self) {
+        let pending = {
+            let mut state = self.state();
+            if !state.initialized || state.fsm_mode != TouchFSMMode::Timer {
+                return;
+            }
+            state.measure()
+        };
+        pending.run(&self.isr);
+    }
+
+    /// Whether the touch module is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.state().initialized
+    }
+
+    /// Whether the touch interrupt is enabled.
+    pub fn is_intr_enabled(&self) -> bool {
+        self.state().intr_enabled
+    }
+
+    /// Whether `pad_io_init` was called for the pad.
+    pub fn is_io_initialized(&self, touch_num: TouchPadChannel) -> bool {
+        self.state()
+            .channels
+            .get(touch_num as usize)
+            .is_some_and(|channel| channel.io_init)
+    }
+
+    /// Number of registered interrupt handlers.
+    pub fn isr_count(&self) -> usize {
+        self.state().isr_handlers.len()
+    }
+
+    /// Whether a filter read callback is installed.
+    pub fn has_filter_read_cb(&self) -> bool {
+        self.state().filter_cb.is_some()
+    }
+}
+
+impl TouchHal for SimTouchHal {
+    fn init(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        if state.initialized {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        // Like the hardware, a fresh init loses the configuration but not the pads themselves.
+        let mut fresh = SimState::new();
+        for (channel, previous) in fresh.channels.iter_mut().zip(state.channels.iter()) {
+            channel.raw = previous.raw;
+            channel.noise = previous.noise;
+            channel.filtered = previous.raw;
+        }
+        fresh.wakeup_status = state.wakeup_status;
+        fresh.time_ms = state.time_ms;
+        fresh.stalled = state.stalled;
+        fresh.initialized = true;
+        *state = fresh;
+        Ok(())
+    }
+
+    fn deinit(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.initialized = false;
+        state.isr_handlers.clear();
+        state.filter_period_ms = None;
+        state.filter_cb = None;
+        state.intr_enabled = false;
+        Ok(())
+    }
+
+    fn sw_start(&self) -> Result<(), EspErr> {
+        let pending = {
+            let mut state = self.state();
+            state.ready()?;
+            if state.fsm_mode != TouchFSMMode::SW {
+                return Ok(());
+            }
+            if state.stalled {
+                state.meas_done = false;
+                return Ok(());
+            }
+            state.measure()
+        };
+        pending.run(&self.isr);
+        Ok(())
+    }
+
+    fn set_trigger_source(&self, src: TouchTriggerSource) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        if src == TouchTriggerSource::Max {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        state.trigger_source = src;
+        Ok(())
+    }
+
+    fn get_trigger_source(&self) -> Result<TouchTriggerSource, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.trigger_source)
+    }
+
+    fn set_trigger_mode(&self, mode: TouchTriggerMode) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        if mode == TouchTriggerMode::Max {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        state.trigger_mode = mode;
+        Ok(())
+    }
+
+    fn get_trigger_mode(&self) -> Result<TouchTriggerMode, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.trigger_mode)
+    }
+
+    fn set_thresh(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+        self.state().channel(touch_num)?.thresh = threshold;
+        Ok(())
+    }
+
+    fn get_thresh(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        Ok(self.state().channel(touch_num)?.thresh)
+    }
+
+    fn set_meas_time(&self, sleep_cycle: u16, meas_cycle: u16) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.meas_time = MeasTime {
+            sleep_cycle,
+            meas_cycle,
+        };
+        Ok(())
+    }
+
+    fn get_meas_time(&self) -> Result<MeasTime, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.meas_time)
+    }
+
+    fn set_measurement_interval(&self, interval_cycle: u16) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.meas_time.sleep_cycle = interval_cycle;
+        Ok(())
+    }
+
+    fn get_measurement_interval(&self) -> Result<u16, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.meas_time.sleep_cycle)
+    }
+
+    fn set_measurement_clock_cycles(&self, clock_cycle: u16) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.meas_time.meas_cycle = clock_cycle;
+        Ok(())
+    }
+
+    fn get_measurement_clock_cycles(&self) -> Result<u16, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.meas_time.meas_cycle)
+    }
+
+    fn set_voltage(
+        &self,
+        touch_high_voltage: TouchHighVoltage,
+        touch_low_voltage: TouchLowVoltage,
+        touch_voltage_attenuation: TouchVoltageAttenuation,
+    ) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        if touch_high_voltage == TouchHighVoltage::Max
+            || touch_low_voltage == TouchLowVoltage::Max
+            || touch_voltage_attenuation == TouchVoltageAttenuation::Max
+        {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        if touch_high_voltage != TouchHighVoltage::Keep {
+            state.voltage.high = touch_high_voltage;
+        }
+        if touch_low_voltage != TouchLowVoltage::Keep {
+            state.voltage.low = touch_low_voltage;
+        }
+        if touch_voltage_attenuation != TouchVoltageAttenuation::Keep {
+            state.voltage.attenuation = touch_voltage_attenuation;
+        }
+        Ok(())
+    }
+
+    fn get_voltage(&self) -> Result<VoltageConfig, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.voltage)
+    }
+
+    fn config(&self, touch_num: TouchPadChannel, threshold: u16) -> Result<(), EspErr> {
+        let mut state = self.state();
+        let channel = state.channel(touch_num)?;
+        // Like `touch_hal_config`, the pad is put back to the default charge/discharge speed and
+        // added to both sets.
+        channel.thresh = threshold;
+        channel.cnt_mode = SIM_DEFAULT_CNT_MODE;
+        channel.io_init = true;
+        state.group_mask.set1_mask.insert(touch_num);
+        state.group_mask.set2_mask.insert(touch_num);
+        state.group_mask.en_mask.insert(touch_num);
+        Ok(())
+    }
+
+    fn read(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        let mut state = self.state();
+        let channel = state.measured(touch_num)?;
+        if channel.raw == 0 {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        let value = if channel.noise_high {
+            channel.raw.saturating_add(channel.noise)
+        } else {
+            channel.raw.saturating_sub(channel.noise).max(1)
+        };
+        channel.noise_high = !channel.noise_high;
+        Ok(value)
+    }
+
+    fn read_filtered(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        let mut state = self.state();
+        if state.filter_period_ms.is_none() {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        match state.measured(touch_num)?.filtered {
+            0 => Err(EspErr::EspErrInvalidState),
+            filtered => Ok(filtered),
+        }
+    }
+
+    fn read_raw_data(&self, touch_num: TouchPadChannel) -> Result<u16, EspErr> {
+        self.read(touch_num)
+    }
+
+    fn filter_start(&self, filter_period_ms: u32) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        if filter_period_ms == 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        state.filter_period_ms = Some(filter_period_ms);
+        for channel in state.channels.iter_mut() {
+            channel.filtered = channel.raw;
+        }
+        Ok(())
+    }
+
+    fn set_filter_period(&self, new_period_ms: u32) -> Result<(), EspErr> {
+        let mut state = self.state();
+        if state.filter_period_ms.is_none() {
+            return Err(EspErr::EspErrInvalidState);
+        }
+        if new_period_ms == 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        state.filter_period_ms = Some(new_period_ms);
+        Ok(())
+    }
+
+    fn get_filter_period(&self) -> Result<u32, EspErr> {
+        self.state()
+            .filter_period_ms
+            .ok_or(EspErr::EspErrInvalidState)
+    }
+
+    fn filter_stop(&self) -> Result<(), EspErr> {
+        let state = self.state();
+        state.filter_period_ms.ok_or(EspErr::EspErrInvalidState)?;
+        Ok(())
+    }
+
+    fn filter_delete(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.filter_period_ms.ok_or(EspErr::EspErrInvalidState)?;
+        state.filter_period_ms = None;
+        state.filter_cb = None;
+        Ok(())
+    }
+
+    fn set_filter_read_cb(&self, filter_cb: FilterCbT) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.filter_cb = filter_cb;
+        Ok(())
+    }
+
+    fn set_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.group_mask.set1_mask |= set1_mask;
+        state.group_mask.set2_mask |= set2_mask;
+        state.group_mask.en_mask |= en_mask;
+        Ok(())
+    }
+
+    fn get_group_mask(&self) -> Result<GroupMask, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.group_mask)
+    }
+
+    fn clear_group_mask(
+        &self,
+        set1_mask: ChannelSet,
+        set2_mask: ChannelSet,
+        en_mask: ChannelSet,
+    ) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.group_mask.set1_mask -= set1_mask;
+        state.group_mask.set2_mask -= set2_mask;
+        state.group_mask.en_mask -= en_mask;
+        Ok(())
+    }
+
+    fn intr_enable(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.intr_enabled = true;
+        Ok(())
+    }
+
+    fn intr_disable(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.intr_enabled = false;
+        Ok(())
+    }
+
+    fn intr_clear(&self) -> Result<(), EspErr> {
+        self.state().ready()
+    }
+
+    fn pad_io_init(&self, touch_num: TouchPadChannel) -> Result<(), EspErr> {
+        self.state().channel(touch_num)?.io_init = true;
+        Ok(())
+    }
+
+    fn set_cnt_mode(
+        &self,
+        touch_num: TouchPadChannel,
+        slope: TouchCountSlope,
+        opt: TouchTieOption,
+    ) -> Result<(), EspErr> {
+        if slope == TouchCountSlope::Max || opt == TouchTieOption::Max {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        self.state().channel(touch_num)?.cnt_mode = CntMode { slope, opt };
+        Ok(())
+    }
+
+    fn get_cnt_mode(&self, touch_num: TouchPadChannel) -> Result<CntMode, EspErr> {
+        Ok(self.state().channel(touch_num)?.cnt_mode)
+    }
+
+    fn get_wakeup_status(&self) -> Result<TouchPadChannel, EspErr> {
+        self.state()
+            .wakeup_status
+            .ok_or(EspErr::EspErrInvalidResponse)
+    }
+
+    fn set_fsm_mode(&self, mode: TouchFSMMode) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        if mode == TouchFSMMode::Max {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        state.fsm_mode = mode;
+        Ok(())
+    }
+
+    fn get_fsm_mode(&self) -> Result<TouchFSMMode, EspErr> {
+        let state = self.state();
+        state.ready()?;
+        Ok(state.fsm_mode)
+    }
+
+    fn clear_status(&self) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        state.status = 0;
+        Ok(())
+    }
+
+    fn get_status(&self) -> ChannelSet {
+        ChannelSet::from_bits_truncate(self.state().status)
+    }
+
+    fn meas_is_done(&self) -> bool {
+        self.state().meas_done
+    }
+
+    fn isr_register(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+        let mut state = self.state();
+        state.ready()?;
+        let handler = isr_handler.ok_or(EspErr::EspErrInvalidArg)?;
+        state.isr_handlers.push((handler, arg as usize));
+        Ok(())
+    }
+
+    fn isr_deregister(&self, isr_handler: IntrHandler, arg: *mut c_void) -> Result<(), EspErr> {
+        let mut state = self.state();
+        let handler = isr_handler.ok_or(EspErr::EspErrInvalidState)?;
+        let position = state
+            .isr_handlers
+            .iter()
+            .position(|&(registered, registered_arg)| {
+                registered as usize == handler as usize && registered_arg == arg as usize
+            })
+            .ok_or(EspErr::EspErrInvalidState)?;
+        state.isr_handlers.remove(position);
+        Ok(())
+    }
+
+    fn timestamp_ms(&self) -> u32 {
+        self.state().time_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_resets_the_pad() {
+        let sim = SimTouchHal::new();
+        assert_eq!(sim.config(TouchPadChannel::Num1, 500), Err(EspErr::EspFail));
+        sim.init().unwrap();
+        sim.set_cnt_mode(
+            TouchPadChannel::Num1,
+            TouchCountSlope::Slope2,
+            TouchTieOption::High,
+        )
+        .unwrap();
+        sim.config(TouchPadChannel::Num1, 500).unwrap();
+
+        assert_eq!(
+            sim.get_cnt_mode(TouchPadChannel::Num1),
+            Ok(SIM_DEFAULT_CNT_MODE)
+        );
+        assert_eq!(sim.get_thresh(TouchPadChannel::Num1), Ok(500));
+        assert!(sim.is_io_initialized(TouchPadChannel::Num1));
+        let channel = ChannelSet::from(TouchPadChannel::Num1);
+        assert_eq!(
+            sim.get_group_mask(),
+            Ok(GroupMask {
+                set1_mask: channel,
+                set2_mask: channel,
+                en_mask: channel,
+            })
+        );
+    }
+
+    #[test]
+    fn measurement_fires_the_isr() {
+        unsafe extern "C" fn count(arg: *mut c_void) {
+            *(arg as *mut u32) += 1;
+        }
+        let sim = SimTouchHal::new();
+        sim.init().unwrap();
+        sim.config(TouchPadChannel::Num0, 500).unwrap();
+        let mut calls = 0u32;
+        let arg = &mut calls as *mut u32 as *mut c_void;
+        sim.isr_register(Some(count), arg).unwrap();
+        sim.intr_enable().unwrap();
+
+        sim.tick();
+        sim.set_raw(TouchPadChannel::Num0, 400);
+        sim.tick();
+        assert_eq!(sim.get_status(), ChannelSet::from(TouchPadChannel::Num0));
+        sim.isr_deregister(Some(count), arg).unwrap();
+        sim.tick();
+        assert_eq!(calls, 1);
+    }
+}
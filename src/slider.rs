@@ -0,0 +1,286 @@
+use crate::baseline::BaselineTracker;
+use crate::channel_set::ChannelSet;
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::EspErr;
+
+/// Events emitted by a `TouchSlider`. Positions are in `0..=resolution`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SliderEvent {
+    TouchDown {
+        position: u16,
+    },
+    /// The finger moved, `velocity` is in positions per second.
+    Move {
+        position: u16,
+        velocity: f32,
+    },
+    TouchUp {
+        position: u16,
+    },
+}
+
+/// Tuning of a `TouchSlider`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliderConfig {
+    /// Pads of the slider, in order from position 0 to `resolution`. At least one, each listed
+    /// once.
+    pub channels: Vec<TouchPadChannel>,
+    /// Highest position reported.
+    pub resolution: u16,
+    /// Delta (baseline - raw) from which a pad counts as touched, must be positive.
+    pub touch_delta: i32,
+}
+
+/// Index of the strongest pad and the interpolated position around it, in pad units.
+/// Deltas below `touch_delta` are ignored, `None` when no pad is touched.
+pub(crate) fn centroid(deltas: &[i32], touch_delta: i32) -> Option<f32> {
+    let (peak, _) = deltas
+        .iter()
+        .enumerate()
+        .filter(|(_, &delta)| delta >= touch_delta)
+        .max_by_key(|(_, &delta)| delta)?;
+    let first = peak.saturating_sub(1);
+    let last = (peak + 1).min(deltas.len() - 1);
+    let mut sum = 0.0;
+    let mut weighted = 0.0;
+    for (index, &delta) in deltas.iter().enumerate().take(last + 1).skip(first) {
+        let delta = delta.max(0) as f32;
+        sum += delta;
+        weighted += delta * index as f32;
+    }
+    Some(weighted / sum)
+}
+
+/// Check a list of pads: not empty, no `Max` and no pad listed twice.
+pub(crate) fn check_channels(channels: &[TouchPadChannel]) -> Result<(), EspErr> {
+    let mut seen = ChannelSet::new();
+    for &channel in channels {
+        if channel == TouchPadChannel::Max || seen.contains(channel) {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        seen.insert(channel);
+    }
+    if seen.is_empty() {
+        return Err(EspErr::EspErrInvalidArg);
+    }
+    Ok(())
+}
+
+/// Deltas of `channels`, in their order.
+pub(crate) fn channel_deltas(
+    channels: &[TouchPadChannel],
+    deltas: &[i32; TouchPadChannel::ALL.len()],
+) -> Vec<i32> {
+    channels
+        .iter()
+        .map(|&channel| deltas.get(channel as usize).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Map a position in pad units on a line of `len` pads to `0..=resolution`.
+pub(crate) fn scale(index: f32, len: usize, resolution: u16) -> u16 {
+    let span = len.saturating_sub(1).max(1) as f32;
+    (index / span * resolution as f32).round() as u16
+}
+
+/// Whether the pads over `touch_delta` form a single run of neighbours.
+pub(crate) fn is_contiguous(deltas: &[i32], touch_delta: i32) -> bool {
+    let active: Vec<usize> = deltas
+        .iter()
+        .enumerate()
+        .filter(|(_, &delta)| delta >= touch_delta)
+        .map(|(index, _)| index)
+        .collect();
+    active.windows(2).all(|pair| pair[1] == pair[0] + 1)
+}
+
+/// Linear slider made of adjacent pads. The position is interpolated between pads from the
+/// deltas to the baselines (see `BaselineTracker::deltas`), so it moves continuously instead of
+/// jumping from pad to pad.
+#[derive(Debug, Clone)]
+pub struct TouchSlider {
+    config: SliderConfig,
+    position: Option<u16>,
+    /// Time of the last event, velocities are measured from there.
+    last_ms: u32,
+}
+
+impl TouchSlider {
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG `touch_delta` is not positive, or `channels` is empty, holds `Max`
+    ///   or a pad twice
+    pub fn new(config: SliderConfig) -> Result<Self, EspErr> {
+        if config.touch_delta <= 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        check_channels(&config.channels)?;
+        Ok(TouchSlider {
+            config,
+            position: None,
+            last_ms: 0,
+        })
+    }
+
+    pub fn config(&self) -> &SliderConfig {
+        &self.config
+    }
+
+    /// Current position, `None` when the slider is not touched.
+    pub fn position(&self) -> Option<u16> {
+        self.position
+    }
+
+    /// Feed the deltas of every channel, indexed by `TouchPadChannel`, taken at `timestamp_ms`.
+    /// Readings where pads that are not neighbours are touched at the same time are rejected
+    /// and leave the slider unchanged.
+    pub fn update(
+        &mut self,
+        timestamp_ms: u32,
+        deltas: &[i32; TouchPadChannel::ALL.len()],
+    ) -> Option<SliderEvent> {
+        let deltas = channel_deltas(&self.config.channels, deltas);
+        if !is_contiguous(&deltas, self.config.touch_delta) {
+            return None;
+        }
+
+        let position = centroid(&deltas, self.config.touch_delta)
+            .map(|index| scale(index, deltas.len(), self.config.resolution));
+        let dt_ms = timestamp_ms.wrapping_sub(self.last_ms);
+        let event = match (self.position, position) {
+            (None, Some(position)) => Some(SliderEvent::TouchDown { position }),
+            (Some(previous), Some(position)) if previous != position => {
+                let velocity = if dt_ms == 0 {
+                    0.0
+                } else {
+                    (position as f32 - previous as f32) * 1000.0 / dt_ms as f32
+                };
+                Some(SliderEvent::Move { position, velocity })
+            }
+            (Some(previous), None) => Some(SliderEvent::TouchUp { position: previous }),
+            _ => None,
+        };
+        self.position = position;
+        if event.is_some() {
+            self.last_ms = timestamp_ms;
+        }
+        event
+    }
+
+    /// Feed the current deltas of a `BaselineTracker`.
+    pub fn update_from(
+        &mut self,
+        timestamp_ms: u32,
+        tracker: &BaselineTracker,
+    ) -> Option<SliderEvent> {
+        self.update(timestamp_ms, &tracker.deltas())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(touch_delta: i32) -> SliderConfig {
+        SliderConfig {
+            channels: vec![TouchPadChannel::Num0, TouchPadChannel::Num1],
+            resolution: 100,
+            touch_delta,
+        }
+    }
+
+    #[test]
+    fn touch_delta_must_be_positive() {
+        assert_eq!(
+            TouchSlider::new(config(0)).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(TouchSlider::new(config(-5)).is_err());
+
+        let mut slider = TouchSlider::new(config(1)).unwrap();
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[1] = 40;
+        assert_eq!(
+            slider.update(0, &deltas),
+            Some(SliderEvent::TouchDown { position: 100 })
+        );
+    }
+
+    /// Slider on `Num2..=Num5`, 100 positions per pad.
+    fn slider() -> TouchSlider {
+        TouchSlider::new(SliderConfig {
+            channels: TouchPadChannel::ALL[2..6].to_vec(),
+            resolution: 300,
+            touch_delta: 10,
+        })
+        .unwrap()
+    }
+
+    /// Deltas of `Num2..=Num5`, the other channels at 0.
+    fn deltas(pads: [i32; 4]) -> [i32; TouchPadChannel::ALL.len()] {
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[2..6].copy_from_slice(&pads);
+        deltas
+    }
+
+    #[test]
+    fn position_is_interpolated() {
+        let mut slider = slider();
+        assert_eq!(
+            slider.update(0, &deltas([0, 100, 0, 0])),
+            Some(SliderEvent::TouchDown { position: 100 })
+        );
+        assert_eq!(
+            slider.update(100, &deltas([0, 60, 20, 0])),
+            Some(SliderEvent::Move {
+                position: 125,
+                velocity: 250.0
+            })
+        );
+        slider.update(200, &deltas([0, 50, 50, 0]));
+        assert_eq!(slider.position(), Some(150));
+        // Below `touch_delta`, a neighbour still weighs in the interpolation.
+        slider.update(300, &deltas([0, 0, 90, 5]));
+        assert_eq!(slider.position(), Some(205));
+        assert_eq!(
+            slider.update(400, &deltas([0; 4])),
+            Some(SliderEvent::TouchUp { position: 205 })
+        );
+    }
+
+    #[test]
+    fn ends_scale_to_the_range() {
+        let mut slider = slider();
+        slider.update(0, &deltas([80, 0, 0, 0]));
+        assert_eq!(slider.position(), Some(0));
+        slider.update(10, &deltas([0, 0, 0, 80]));
+        assert_eq!(slider.position(), Some(300));
+        assert_eq!(scale(1.0, 4, 1000), 333);
+        assert_eq!(scale(0.0, 1, 1000), 0);
+    }
+
+    #[test]
+    fn non_adjacent_pads_are_rejected() {
+        let mut slider = slider();
+        slider.update(0, &deltas([0, 100, 0, 0]));
+        assert_eq!(slider.update(10, &deltas([100, 0, 100, 0])), None);
+        assert_eq!(slider.position(), Some(100));
+    }
+
+    #[test]
+    fn channels_are_checked() {
+        let config = |channels: Vec<TouchPadChannel>| SliderConfig {
+            channels,
+            resolution: 100,
+            touch_delta: 10,
+        };
+        use TouchPadChannel::*;
+        assert!(TouchSlider::new(config(vec![])).is_err());
+        assert!(TouchSlider::new(config(vec![Num1, Num2, Num1])).is_err());
+        assert_eq!(
+            TouchSlider::new(config(vec![Num1, Max])).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(TouchSlider::new(config(vec![Num3])).is_ok());
+    }
+}
@@ -0,0 +1,43 @@
+use crate::touch_pad_error::*;
+use std::collections::HashMap;
+
+/// Small key-value store for blobs, used to persist calibration data. `NvsStorage` stores them
+/// in the NVS flash partition of the device, `MemoryStorage` in memory.
+pub trait KvStorage {
+    /// Blob stored under `key`, `None` if there is none.
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, EspErr>;
+
+    /// Store `value` under `key`, replacing the previous blob.
+    fn set_blob(&mut self, key: &str, value: &[u8]) -> Result<(), EspErr>;
+
+    /// Remove the blob stored under `key`, if any.
+    fn remove(&mut self, key: &str) -> Result<(), EspErr>;
+}
+
+/// `KvStorage` kept in memory, for tests and the simulator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStorage {
+    blobs: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KvStorage for MemoryStorage {
+    fn get_blob(&self, key: &str) -> Result<Option<Vec<u8>>, EspErr> {
+        Ok(self.blobs.get(key).cloned())
+    }
+
+    fn set_blob(&mut self, key: &str, value: &[u8]) -> Result<(), EspErr> {
+        self.blobs.insert(key.to_owned(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), EspErr> {
+        self.blobs.remove(key);
+        Ok(())
+    }
+}
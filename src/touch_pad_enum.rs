@@ -1,5 +1,93 @@
+use crate::touch_pad_error::EspErr;
+use std::fmt;
+use std::str::FromStr;
+
+/// Conversions shared by the touch enums, built on their `from_raw`:
+///
+/// * `ALL`, every value without the `Keep` and `Max` sentinels
+/// * `TryFrom<u32>` / `TryFrom<i32>` from the raw IDF value
+/// * `Display` with the physical unit, e.g. `2.7V` or `slope 7`, and `keep` / `max` for the
+///   sentinels
+/// * `FromStr` accepting the displayed text, the variant name or the raw value, ignoring case.
+///   The sentinels are parsed from their text or name only, so every value round-trips through
+///   `Display`, while their raw value is rejected as in `TryFrom`
+///
+/// Conversions of out of range values fail with ESP_ERR_INVALID_ARG.
+macro_rules! touch_enum {
+    ($name:ident: $raw:ty { $($variant:ident => $text:literal),+ $(,)? } $($sentinel:ident => $sentinel_text:literal),*) => {
+        impl $name {
+            /// Every value, without the sentinels.
+            pub const ALL: [$name; [$(stringify!($variant)),+].len()] = [$($name::$variant),+];
+
+            /// Name of each value of `ALL`.
+            const NAMES: [&'static str; [$(stringify!($variant)),+].len()] =
+                [$(stringify!($variant)),+];
+
+            fn from_raw_i64(value: i64) -> Result<Self, EspErr> {
+                <$raw>::try_from(value)
+                    .ok()
+                    .and_then(Self::from_raw)
+                    .ok_or(EspErr::EspErrInvalidArg)
+            }
+        }
+
+        impl TryFrom<u32> for $name {
+            type Error = EspErr;
+
+            fn try_from(value: u32) -> Result<Self, EspErr> {
+                Self::from_raw_i64(value as i64)
+            }
+        }
+
+        impl TryFrom<i32> for $name {
+            type Error = EspErr;
+
+            fn try_from(value: i32) -> Result<Self, EspErr> {
+                Self::from_raw_i64(value as i64)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $($name::$variant => $text,)+
+                    $($name::$sentinel => $sentinel_text,)*
+                })
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = EspErr;
+
+            fn from_str(text: &str) -> Result<Self, EspErr> {
+                let text = text.trim();
+                if let Ok(value) = text.parse::<i64>() {
+                    return Self::from_raw_i64(value);
+                }
+                $(
+                    if $sentinel_text.eq_ignore_ascii_case(text)
+                        || stringify!($sentinel).eq_ignore_ascii_case(text)
+                    {
+                        return Ok($name::$sentinel);
+                    }
+                )*
+                Self::ALL
+                    .into_iter()
+                    .zip(Self::NAMES)
+                    .find(|(value, name)| {
+                        value.to_string().eq_ignore_ascii_case(text)
+                            || name.eq_ignore_ascii_case(text)
+                    })
+                    .map(|(value, _)| value)
+                    .ok_or(EspErr::EspErrInvalidArg)
+            }
+        }
+    };
+}
+
 /// Touch pad channel
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchPadChannel {
     /// GPIO4(ESP32)
     /// Touch pad channel 0 is GPIO4(ESP32)
@@ -36,8 +124,41 @@ pub enum TouchPadChannel {
     Max = 10,
 }
 
+impl TouchPadChannel {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchPadChannel::Num0),
+            1 => Some(TouchPadChannel::Num1),
+            2 => Some(TouchPadChannel::Num2),
+            3 => Some(TouchPadChannel::Num3),
+            4 => Some(TouchPadChannel::Num4),
+            5 => Some(TouchPadChannel::Num5),
+            6 => Some(TouchPadChannel::Num6),
+            7 => Some(TouchPadChannel::Num7),
+            8 => Some(TouchPadChannel::Num8),
+            9 => Some(TouchPadChannel::Num9),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchPadChannel: u32 {
+    Num0 => "channel 0",
+    Num1 => "channel 1",
+    Num2 => "channel 2",
+    Num3 => "channel 3",
+    Num4 => "channel 4",
+    Num5 => "channel 5",
+    Num6 => "channel 6",
+    Num7 => "channel 7",
+    Num8 => "channel 8",
+    Num9 => "channel 9",
+} Max => "max");
+
 /// Touch sensor high reference voltage
 #[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchHighVoltage {
     /// No change
     /// Touch sensor high reference voltage, no change
@@ -59,8 +180,29 @@ pub enum TouchHighVoltage {
     Max = 4,
 }
 
+impl TouchHighVoltage {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(TouchHighVoltage::V2_4),
+            1 => Some(TouchHighVoltage::V2_5),
+            2 => Some(TouchHighVoltage::V2_6),
+            3 => Some(TouchHighVoltage::V2_7),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchHighVoltage: i32 {
+    V2_4 => "2.4V",
+    V2_5 => "2.5V",
+    V2_6 => "2.6V",
+    V2_7 => "2.7V",
+} Keep => "keep", Max => "max");
+
 /// Touch sensor low reference voltage
 #[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchLowVoltage {
     /// No change
     /// Touch sensor low reference voltage, no change
@@ -82,8 +224,29 @@ pub enum TouchLowVoltage {
     Max = 4,
 }
 
+impl TouchLowVoltage {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(TouchLowVoltage::V0_5),
+            1 => Some(TouchLowVoltage::V0_6),
+            2 => Some(TouchLowVoltage::V0_7),
+            3 => Some(TouchLowVoltage::V0_8),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchLowVoltage: i32 {
+    V0_5 => "0.5V",
+    V0_6 => "0.6V",
+    V0_7 => "0.7V",
+    V0_8 => "0.8V",
+} Keep => "keep", Max => "max");
+
 /// Touch sensor high reference voltage attenuation
 #[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchVoltageAttenuation {
     /// No change
     /// Touch sensor high reference voltage attenuation, no change
@@ -105,8 +268,29 @@ pub enum TouchVoltageAttenuation {
     Max = 4,
 }
 
+impl TouchVoltageAttenuation {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(TouchVoltageAttenuation::V1_5),
+            1 => Some(TouchVoltageAttenuation::V1_0),
+            2 => Some(TouchVoltageAttenuation::V0_5),
+            3 => Some(TouchVoltageAttenuation::V0_0),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchVoltageAttenuation: i32 {
+    V1_5 => "1.5V",
+    V1_0 => "1.0V",
+    V0_5 => "0.5V",
+    V0_0 => "0.0V",
+} Keep => "keep", Max => "max");
+
 /// Touch sensor charge/discharge speed
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchCountSlope {
     /// Always zero
     /// Touch sensor charge/discharge speed, always zero
@@ -137,8 +321,37 @@ pub enum TouchCountSlope {
     Max = 8,
 }
 
+impl TouchCountSlope {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchCountSlope::Slope0),
+            1 => Some(TouchCountSlope::Slope1),
+            2 => Some(TouchCountSlope::Slope2),
+            3 => Some(TouchCountSlope::Slope3),
+            4 => Some(TouchCountSlope::Slope4),
+            5 => Some(TouchCountSlope::Slope5),
+            6 => Some(TouchCountSlope::Slope6),
+            7 => Some(TouchCountSlope::Slope7),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchCountSlope: u32 {
+    Slope0 => "slope 0",
+    Slope1 => "slope 1",
+    Slope2 => "slope 2",
+    Slope3 => "slope 3",
+    Slope4 => "slope 4",
+    Slope5 => "slope 5",
+    Slope6 => "slope 6",
+    Slope7 => "slope 7",
+} Max => "max");
+
 /// Touch sensor initial charge level
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchTieOption {
     /// Low level
     /// Initial level of charging voltage, low level
@@ -151,8 +364,25 @@ pub enum TouchTieOption {
     Max = 2,
 }
 
+impl TouchTieOption {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchTieOption::Low),
+            1 => Some(TouchTieOption::High),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchTieOption: u32 {
+    Low => "low",
+    High => "high",
+} Max => "max");
+
 /// Touch sensor FSM mode
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchFSMMode {
     /// Start touch FSM by timer
     /// To start touch FSM by timer
@@ -165,8 +395,25 @@ pub enum TouchFSMMode {
     Max = 2,
 }
 
+impl TouchFSMMode {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchFSMMode::Timer),
+            1 => Some(TouchFSMMode::SW),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchFSMMode: u32 {
+    Timer => "timer",
+    SW => "sw",
+} Max => "max");
+
 /// Touch trigger mode
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchTriggerMode {
     /// Touch interrupt will happen if counter value is less than threshold
     /// Touch interrupt will happen if counter value is less than threshold.
@@ -179,8 +426,25 @@ pub enum TouchTriggerMode {
     Max = 2,
 }
 
+impl TouchTriggerMode {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchTriggerMode::Below),
+            1 => Some(TouchTriggerMode::Above),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchTriggerMode: u32 {
+    Below => "below",
+    Above => "above",
+} Max => "max");
+
 /// Touch trigger source
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TouchTriggerSource {
     /// Wakeup interrupt is generated if both SET1 and SET2 are "touched"
     /// Wakeup interrupt is generated if both SET1 and SET2 are \"touched\"
@@ -193,6 +457,22 @@ pub enum TouchTriggerSource {
     Max = 2,
 }
 
+impl TouchTriggerSource {
+    /// Convert a raw IDF value, `None` if it is out of range.
+    pub(crate) fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(TouchTriggerSource::Both),
+            1 => Some(TouchTriggerSource::Set1),
+            _ => None,
+        }
+    }
+}
+
+touch_enum!(TouchTriggerSource: u32 {
+    Both => "both",
+    Set1 => "set1",
+} Max => "max");
+
 /// GPIO port
 #[repr(u32)]
 pub enum GPIOPort {
@@ -204,91 +484,114 @@ pub enum GPIOPort {
     Max = 1,
 }
 
-/// GPIO number type
+/// GPIO number type, covering the ESP32, ESP32-S2 and ESP32-S3. Not every number exists on
+/// every chip, see `GpioNum::exists`. `GpioNumMax` is 49, one past GPIO48 of the ESP32-S3; it
+/// was 40 when only the ESP32 was covered.
 #[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GpioNum {
     /// < Use to signal not connected to S/W
     GpioNumNc = -1,
-    /// < GPIO0, input and output
+    /// < GPIO0
     GpioNum0 = 0,
-    /// < GPIO1, input and output
+    /// < GPIO1
     GpioNum1 = 1,
-    /// < GPIO2, input and output
+    /// < GPIO2
     GpioNum2 = 2,
-    /// < GPIO3, input and output
+    /// < GPIO3
     GpioNum3 = 3,
-    /// < GPIO4, input and output
+    /// < GPIO4
     GpioNum4 = 4,
-    /// < GPIO5, input and output
+    /// < GPIO5
     GpioNum5 = 5,
-    /// < GPIO6, input and output
+    /// < GPIO6
     GpioNum6 = 6,
-    /// < GPIO7, input and output
+    /// < GPIO7
     GpioNum7 = 7,
-    /// < GPIO8, input and output
+    /// < GPIO8
     GpioNum8 = 8,
-    /// < GPIO9, input and output
+    /// < GPIO9
     GpioNum9 = 9,
-    /// < GPIO10, input and output
+    /// < GPIO10
     GpioNum10 = 10,
-    /// < GPIO11, input and output
+    /// < GPIO11
     GpioNum11 = 11,
-    /// < GPIO12, input and output
+    /// < GPIO12
     GpioNum12 = 12,
-    /// < GPIO13, input and output
+    /// < GPIO13
     GpioNum13 = 13,
-    /// < GPIO14, input and output
+    /// < GPIO14
     GpioNum14 = 14,
-    /// < GPIO15, input and output
+    /// < GPIO15
     GpioNum15 = 15,
-    /// < GPIO16, input and output
+    /// < GPIO16
     GpioNum16 = 16,
-    /// < GPIO17, input and output
+    /// < GPIO17
     GpioNum17 = 17,
-    /// < GPIO18, input and output
+    /// < GPIO18
     GpioNum18 = 18,
-    /// < GPIO19, input and output
+    /// < GPIO19
     GpioNum19 = 19,
-    /// < GPIO20, input and output
+    /// < GPIO20
     GpioNum20 = 20,
-    /// < GPIO21, input and output
+    /// < GPIO21
     GpioNum21 = 21,
-    /// < GPIO22, input and output
+    /// < GPIO22
     GpioNum22 = 22,
-    /// < GPIO23, input and output
+    /// < GPIO23
     GpioNum23 = 23,
-    /// < GPIO25, input and output
+    /// < GPIO24
+    GpioNum24 = 24,
+    /// < GPIO25
     GpioNum25 = 25,
-    /// < GPIO26, input and output
+    /// < GPIO26
     GpioNum26 = 26,
-    /// < GPIO27, input and output
+    /// < GPIO27
     GpioNum27 = 27,
-    /// < GPIO28, input and output
+    /// < GPIO28
     GpioNum28 = 28,
-    /// < GPIO29, input and output
+    /// < GPIO29
     GpioNum29 = 29,
-    /// < GPIO30, input and output
+    /// < GPIO30
     GpioNum30 = 30,
-    /// < GPIO31, input and output
+    /// < GPIO31
     GpioNum31 = 31,
-    /// < GPIO32, input and output
+    /// < GPIO32
     GpioNum32 = 32,
-    /// < GPIO33, input and output
+    /// < GPIO33
     GpioNum33 = 33,
-    /// < GPIO34, input mode only
+    /// < GPIO34
     GpioNum34 = 34,
-    /// < GPIO35, input mode only
+    /// < GPIO35
     GpioNum35 = 35,
-    /// < GPIO36, input mode only
+    /// < GPIO36
     GpioNum36 = 36,
-    /// < GPIO37, input mode only
+    /// < GPIO37
     GpioNum37 = 37,
-    /// < GPIO38, input mode only
+    /// < GPIO38
     GpioNum38 = 38,
-    /// < GPIO39, input mode only
+    /// < GPIO39
     GpioNum39 = 39,
+    /// < GPIO40
+    GpioNum40 = 40,
+    /// < GPIO41
+    GpioNum41 = 41,
+    /// < GPIO42
+    GpioNum42 = 42,
+    /// < GPIO43
+    GpioNum43 = 43,
+    /// < GPIO44
+    GpioNum44 = 44,
+    /// < GPIO45
+    GpioNum45 = 45,
+    /// < GPIO46
+    GpioNum46 = 46,
+    /// < GPIO47
+    GpioNum47 = 47,
+    /// < GPIO48
+    GpioNum48 = 48,
     /// < Maximum GPIO number
-    GpioNumMax = 40,
+    GpioNumMax = 49,
 }
 
 /// GPIO interrupt type
@@ -464,3 +767,149 @@ pub enum EspSleepMode {
     /// !< Deep sleep mode
     EspSleepModeDeepSleep = 1,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+
+    /// Check that each value parses back from its `Display` text.
+    fn round_trips<T>(values: impl IntoIterator<Item = T>)
+    where
+        T: fmt::Display + FromStr<Err = EspErr> + PartialEq + Debug,
+    {
+        for value in values {
+            assert_eq!(value.to_string().parse::<T>(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn all_skips_the_sentinels() {
+        assert_eq!(
+            TouchHighVoltage::ALL,
+            [
+                TouchHighVoltage::V2_4,
+                TouchHighVoltage::V2_5,
+                TouchHighVoltage::V2_6,
+                TouchHighVoltage::V2_7,
+            ]
+        );
+        assert_eq!(TouchPadChannel::ALL.len(), 10);
+        assert_eq!(TouchCountSlope::ALL.len(), 8);
+        assert_eq!(
+            TouchTieOption::ALL,
+            [TouchTieOption::Low, TouchTieOption::High]
+        );
+        for (index, touch_num) in TouchPadChannel::ALL.into_iter().enumerate() {
+            assert_eq!(touch_num as usize, index);
+        }
+    }
+
+    #[test]
+    fn raw_values_are_checked() {
+        assert_eq!(TouchPadChannel::try_from(9u32), Ok(TouchPadChannel::Num9));
+        assert_eq!(TouchPadChannel::try_from(9i32), Ok(TouchPadChannel::Num9));
+        assert_eq!(
+            TouchPadChannel::try_from(10u32),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            TouchPadChannel::try_from(-1i32),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(TouchHighVoltage::try_from(3i32), Ok(TouchHighVoltage::V2_7));
+        assert_eq!(TouchHighVoltage::try_from(3u32), Ok(TouchHighVoltage::V2_7));
+        // The sentinels are not values of the peripheral.
+        assert_eq!(
+            TouchHighVoltage::try_from(-1i32),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            TouchHighVoltage::try_from(4i32),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            TouchLowVoltage::try_from(u32::MAX),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            TouchFSMMode::try_from(i32::MIN),
+            Err(EspErr::EspErrInvalidArg)
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        assert_eq!(TouchHighVoltage::V2_7.to_string(), "2.7V");
+        assert_eq!(TouchVoltageAttenuation::V0_0.to_string(), "0.0V");
+        assert_eq!(TouchCountSlope::Slope7.to_string(), "slope 7");
+        assert_eq!(TouchPadChannel::Num3.to_string(), "channel 3");
+        assert_eq!(TouchHighVoltage::Keep.to_string(), "keep");
+        assert_eq!(TouchPadChannel::Max.to_string(), "max");
+
+        round_trips(
+            TouchPadChannel::ALL
+                .into_iter()
+                .chain([TouchPadChannel::Max]),
+        );
+        round_trips(
+            TouchHighVoltage::ALL
+                .into_iter()
+                .chain([TouchHighVoltage::Keep, TouchHighVoltage::Max]),
+        );
+        round_trips(
+            TouchLowVoltage::ALL
+                .into_iter()
+                .chain([TouchLowVoltage::Keep, TouchLowVoltage::Max]),
+        );
+        round_trips(
+            TouchVoltageAttenuation::ALL
+                .into_iter()
+                .chain([TouchVoltageAttenuation::Keep, TouchVoltageAttenuation::Max]),
+        );
+        round_trips(
+            TouchCountSlope::ALL
+                .into_iter()
+                .chain([TouchCountSlope::Max]),
+        );
+        round_trips(TouchTieOption::ALL.into_iter().chain([TouchTieOption::Max]));
+        round_trips(TouchFSMMode::ALL.into_iter().chain([TouchFSMMode::Max]));
+        round_trips(
+            TouchTriggerMode::ALL
+                .into_iter()
+                .chain([TouchTriggerMode::Max]),
+        );
+        round_trips(
+            TouchTriggerSource::ALL
+                .into_iter()
+                .chain([TouchTriggerSource::Max]),
+        );
+    }
+
+    #[test]
+    fn text_names_and_raw_values_parse() {
+        for text in [" 2.7v ", "V2_7", "v2_7", "3"] {
+            assert_eq!(text.parse(), Ok(TouchHighVoltage::V2_7));
+        }
+        for text in ["channel 3", "Num3", "3"] {
+            assert_eq!(text.parse(), Ok(TouchPadChannel::Num3));
+        }
+        assert_eq!("KEEP".parse(), Ok(TouchLowVoltage::Keep));
+        assert_eq!("Max".parse(), Ok(TouchTriggerMode::Max));
+        assert_eq!("SW".parse(), Ok(TouchFSMMode::SW));
+        // Raw values of the sentinels are rejected, as by `TryFrom`.
+        assert_eq!(
+            "-1".parse::<TouchHighVoltage>(),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            "10".parse::<TouchPadChannel>(),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!(
+            "2.8V".parse::<TouchHighVoltage>(),
+            Err(EspErr::EspErrInvalidArg)
+        );
+        assert_eq!("".parse::<TouchTieOption>(), Err(EspErr::EspErrInvalidArg));
+    }
+}
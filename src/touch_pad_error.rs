@@ -1,8 +1,10 @@
+use crate::touch_pad_enum::TouchPadChannel;
 use std::fmt;
 use std::fmt::Display;
+use std::io;
 
 #[repr(i32)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EspErr {
     EspOk = 0,
     EspFail = -1,
@@ -24,41 +26,233 @@ pub enum EspErr {
     EspErrFlashBase = 0x6000,
     EspErrHwCryptoBase = 0xc000,
     EspErrMemprotBase = 0xd000,
-    EspErrUnknown,
+    /// Code without a variant of its own, e.g. an `ESP_ERR_WIFI_*` sub-code.
+    EspErrUnknown(UnknownCode),
+}
+
+/// Code of an `EspErrUnknown`. It is only built by `EspErr::try_from`, so a code that has a
+/// variant never ends up here and two errors with the same code always compare equal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownCode(i32);
+
+impl UnknownCode {
+    /// The `esp_err_t` code.
+    pub const fn code(&self) -> i32 {
+        self.0
+    }
+}
+
+/// The code in hex, e.g. `0x3007`.
+impl fmt::Debug for UnknownCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Hex(self.0))
+    }
+}
+
+/// Component range of an error code. Each component owns the 0x1000 codes from its base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EspErrComponent {
+    Wifi,
+    Mesh,
+    Flash,
+    HwCrypto,
+    Memprot,
+}
+
+impl EspErrComponent {
+    /// Size of the code range of a component.
+    const RANGE: i32 = 0x1000;
+
+    /// First code of the component, `ESP_ERR_*_BASE`.
+    pub const fn base(&self) -> i32 {
+        match self {
+            EspErrComponent::Wifi => 0x3000,
+            EspErrComponent::Mesh => 0x4000,
+            EspErrComponent::Flash => 0x6000,
+            EspErrComponent::HwCrypto => 0xc000,
+            EspErrComponent::Memprot => 0xd000,
+        }
+    }
+
+    /// Component whose range holds `code`, `None` for the generic and other component codes.
+    pub const fn of(code: i32) -> Option<Self> {
+        match code & !(Self::RANGE - 1) {
+            0x3000 => Some(EspErrComponent::Wifi),
+            0x4000 => Some(EspErrComponent::Mesh),
+            0x6000 => Some(EspErrComponent::Flash),
+            0xc000 => Some(EspErrComponent::HwCrypto),
+            0xd000 => Some(EspErrComponent::Memprot),
+            _ => None,
+        }
+    }
 }
 
 impl std::error::Error for EspErr {}
 
 impl Display for EspErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.return_message_as_text())
+        match self {
+            EspErr::EspErrUnknown(code) => {
+                write!(f, "{} {:?}", self.return_message_as_text(), code)
+            }
+            _ => write!(f, "{}", self.return_message_as_text()),
+        }
+    }
+}
+
+/// Variant name followed by the code in hex, e.g. `EspErrInvalidArg(0x102)`.
+impl fmt::Debug for EspErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name(), Hex(self.code()))
+    }
+}
+
+/// Signed hexadecimal code, `-0x1` for `ESP_FAIL`.
+struct Hex(i32);
+
+impl Display for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            write!(f, "-{:#x}", self.0.unsigned_abs())
+        } else {
+            write!(f, "{:#x}", self.0)
+        }
+    }
+}
+
+/// Error from an `esp_err_t` code. `ESP_OK` is not an error and is handed back. Codes without a
+/// variant are kept in `EspErrUnknown`.
+impl TryFrom<i32> for EspErr {
+    type Error = i32;
+
+    fn try_from(valeur: i32) -> Result<Self, i32> {
+        Ok(match valeur {
+            0 => return Err(valeur),
+            -1 => EspErr::EspFail,
+            0x101 => EspErr::EspErrNoMem,
+            0x102 => EspErr::EspErrInvalidArg,
+            0x103 => EspErr::EspErrInvalidState,
+            0x104 => EspErr::EspErrInvalidSize,
+            0x105 => EspErr::EspErrNotFound,
+            0x106 => EspErr::EspErrNotSupported,
+            0x107 => EspErr::EspErrTimeout,
+            0x108 => EspErr::EspErrInvalidResponse,
+            0x109 => EspErr::EspErrInvalidCrc,
+            0x10A => EspErr::EspErrInvalidVersion,
+            0x10B => EspErr::EspErrInvalidMac,
+            0x10C => EspErr::EspErrNotFinished,
+            0x10D => EspErr::EspErrNotAllowed,
+            0x3000 => EspErr::EspErrWifiBase,
+            0x4000 => EspErr::EspErrMeshBase,
+            0x6000 => EspErr::EspErrFlashBase,
+            0xC000 => EspErr::EspErrHwCryptoBase,
+            0xD000 => EspErr::EspErrMemprotBase,
+            _ => EspErr::EspErrUnknown(UnknownCode(valeur)),
+        })
+    }
+}
+
+/// `esp_err_t` code of the error.
+impl From<EspErr> for i32 {
+    fn from(err: EspErr) -> Self {
+        err.code()
+    }
+}
+
+/// An `EspError` is never `ESP_OK`, so the conversion cannot fail.
+#[cfg(feature = "esp-idf")]
+impl From<esp_idf_svc::sys::EspError> for EspErr {
+    fn from(err: esp_idf_svc::sys::EspError) -> Self {
+        EspErr::try_from(err.code()).unwrap_or(EspErr::EspErrUnknown(UnknownCode(err.code())))
+    }
+}
+
+/// `EspOk`, which is not an error, is handed back.
+#[cfg(feature = "esp-idf")]
+impl TryFrom<EspErr> for esp_idf_svc::sys::EspError {
+    type Error = EspErr;
+
+    fn try_from(err: EspErr) -> Result<Self, EspErr> {
+        esp_idf_svc::sys::EspError::from(err.code()).ok_or(err)
     }
 }
 
+/// Value converted from a raw IDF value, ESP_ERR_INVALID_RESPONSE if it was out of range.
+pub(crate) fn checked<T>(value: Option<T>) -> Result<T, EspErr> {
+    value.ok_or(EspErr::EspErrInvalidResponse)
+}
+
 impl EspErr {
     pub fn return_message(valeur: i32) -> Result<(), EspErr> {
-        match valeur {
-            0 => Ok(()),
-            -1 => Err(EspErr::EspFail),
-            0x101 => Err(EspErr::EspErrNoMem),
-            0x102 => Err(EspErr::EspErrInvalidArg),
-            0x103 => Err(EspErr::EspErrInvalidState),
-            0x104 => Err(EspErr::EspErrInvalidSize),
-            0x105 => Err(EspErr::EspErrNotFound),
-            0x106 => Err(EspErr::EspErrNotSupported),
-            0x107 => Err(EspErr::EspErrTimeout),
-            0x108 => Err(EspErr::EspErrInvalidResponse),
-            0x109 => Err(EspErr::EspErrInvalidCrc),
-            0x10A => Err(EspErr::EspErrInvalidVersion),
-            0x10B => Err(EspErr::EspErrInvalidMac),
-            0x10C => Err(EspErr::EspErrNotFinished),
-            0x10D => Err(EspErr::EspErrNotAllowed),
-            0x3000 => Err(EspErr::EspErrWifiBase),
-            0x4000 => Err(EspErr::EspErrMeshBase),
-            0x6000 => Err(EspErr::EspErrFlashBase),
-            0xC000 => Err(EspErr::EspErrHwCryptoBase),
-            0xD000 => Err(EspErr::EspErrMemprotBase),
-            _ => Err(EspErr::EspErrUnknown),
+        match EspErr::try_from(valeur) {
+            Ok(err) => Err(err),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// `esp_err_t` code of the error.
+    pub const fn code(&self) -> i32 {
+        match self {
+            EspErr::EspOk => 0,
+            EspErr::EspFail => -1,
+            EspErr::EspErrNoMem => 0x101,
+            EspErr::EspErrInvalidArg => 0x102,
+            EspErr::EspErrInvalidState => 0x103,
+            EspErr::EspErrInvalidSize => 0x104,
+            EspErr::EspErrNotFound => 0x105,
+            EspErr::EspErrNotSupported => 0x106,
+            EspErr::EspErrTimeout => 0x107,
+            EspErr::EspErrInvalidResponse => 0x108,
+            EspErr::EspErrInvalidCrc => 0x109,
+            EspErr::EspErrInvalidVersion => 0x10A,
+            EspErr::EspErrInvalidMac => 0x10B,
+            EspErr::EspErrNotFinished => 0x10C,
+            EspErr::EspErrNotAllowed => 0x10D,
+            EspErr::EspErrWifiBase => 0x3000,
+            EspErr::EspErrMeshBase => 0x4000,
+            EspErr::EspErrFlashBase => 0x6000,
+            EspErr::EspErrHwCryptoBase => 0xC000,
+            EspErr::EspErrMemprotBase => 0xD000,
+            EspErr::EspErrUnknown(code) => code.0,
+        }
+    }
+
+    /// Component range of the code, `None` for the generic codes.
+    pub const fn component(&self) -> Option<EspErrComponent> {
+        EspErrComponent::of(self.code())
+    }
+
+    /// Offset of the code from the base of its component, e.g. 7 for `ESP_ERR_WIFI_BASE + 7`.
+    pub const fn component_offset(&self) -> Option<i32> {
+        match self.component() {
+            Some(component) => Some(self.code() - component.base()),
+            None => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            EspErr::EspOk => "EspOk",
+            EspErr::EspFail => "EspFail",
+            EspErr::EspErrNoMem => "EspErrNoMem",
+            EspErr::EspErrInvalidArg => "EspErrInvalidArg",
+            EspErr::EspErrInvalidState => "EspErrInvalidState",
+            EspErr::EspErrInvalidSize => "EspErrInvalidSize",
+            EspErr::EspErrNotFound => "EspErrNotFound",
+            EspErr::EspErrNotSupported => "EspErrNotSupported",
+            EspErr::EspErrTimeout => "EspErrTimeout",
+            EspErr::EspErrInvalidResponse => "EspErrInvalidResponse",
+            EspErr::EspErrInvalidCrc => "EspErrInvalidCrc",
+            EspErr::EspErrInvalidVersion => "EspErrInvalidVersion",
+            EspErr::EspErrInvalidMac => "EspErrInvalidMac",
+            EspErr::EspErrNotFinished => "EspErrNotFinished",
+            EspErr::EspErrNotAllowed => "EspErrNotAllowed",
+            EspErr::EspErrWifiBase => "EspErrWifiBase",
+            EspErr::EspErrMeshBase => "EspErrMeshBase",
+            EspErr::EspErrFlashBase => "EspErrFlashBase",
+            EspErr::EspErrHwCryptoBase => "EspErrHwCryptoBase",
+            EspErr::EspErrMemprotBase => "EspErrMemprotBase",
+            EspErr::EspErrUnknown(_) => "EspErrUnknown",
         }
     }
 
@@ -84,7 +278,291 @@ impl EspErr {
             EspErr::EspErrFlashBase => "Flash error",
             EspErr::EspErrHwCryptoBase => "HW cryptography module error",
             EspErr::EspErrMemprotBase => "Memory Protection API error",
-            EspErr::EspErrUnknown => "Unknown error",
+            EspErr::EspErrUnknown(code) => match EspErrComponent::of(code.0) {
+                Some(EspErrComponent::Wifi) => "WiFi error",
+                Some(EspErrComponent::Mesh) => "MESH error",
+                Some(EspErrComponent::Flash) => "Flash error",
+                Some(EspErrComponent::HwCrypto) => "HW cryptography module error",
+                Some(EspErrComponent::Memprot) => "Memory Protection API error",
+                None => "Unknown error",
+            },
+        }
+    }
+}
+
+/// Error of a touch operation, naming the call that failed.
+#[derive(Debug)]
+pub enum TouchError {
+    /// An IDF call failed. `source` is the code it returned.
+    Esp {
+        /// Driver operation, e.g. `set_thresh`.
+        operation: &'static str,
+        /// Channel the operation was called on, if any.
+        channel: Option<TouchPadChannel>,
+        /// Name and value of the other arguments of the call.
+        args: Vec<(&'static str, String)>,
+        source: EspErr,
+    },
+    /// The touch driver is not initialized.
+    NotInitialized,
+    /// The IIR filter is not running.
+    FilterNotStarted,
+    /// The channel is not in the enable mask, it is not measured.
+    ChannelNotEnabled(TouchPadChannel),
+    /// The channel has no calibration data.
+    NotCalibrated(TouchPadChannel),
+    /// The channel reads 0, its hardware connection is broken.
+    BadConnection(TouchPadChannel),
+    /// The pad is already owned by another `TouchPad` handle.
+    PadTaken(TouchPadChannel),
+    /// `TouchPadChannel::Max` was passed where a channel is expected.
+    InvalidChannel(TouchPadChannel),
+    /// The configuration does not pass `TouchConfig::validate`.
+    InvalidConfig,
+    /// The channel is configured, but its `TouchPad` handle was not passed.
+    PadMissing(TouchPadChannel),
+    /// The thread of the operation could not be spawned.
+    Spawn(io::Error),
+}
+
+/// `Spawn` errors are compared by kind, `io::Error` having no equality.
+impl PartialEq for TouchError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                TouchError::Esp {
+                    operation,
+                    channel,
+                    args,
+                    source,
+                },
+                TouchError::Esp {
+                    operation: other_operation,
+                    channel: other_channel,
+                    args: other_args,
+                    source: other_source,
+                },
+            ) => {
+                operation == other_operation
+                    && channel == other_channel
+                    && args == other_args
+                    && source == other_source
+            }
+            (TouchError::NotInitialized, TouchError::NotInitialized)
+            | (TouchError::FilterNotStarted, TouchError::FilterNotStarted)
+            | (TouchError::InvalidConfig, TouchError::InvalidConfig) => true,
+            (TouchError::ChannelNotEnabled(a), TouchError::ChannelNotEnabled(b))
+            | (TouchError::NotCalibrated(a), TouchError::NotCalibrated(b))
+            | (TouchError::BadConnection(a), TouchError::BadConnection(b))
+            | (TouchError::PadTaken(a), TouchError::PadTaken(b))
+            | (TouchError::InvalidChannel(a), TouchError::InvalidChannel(b))
+            | (TouchError::PadMissing(a), TouchError::PadMissing(b)) => a == b,
+            (TouchError::Spawn(a), TouchError::Spawn(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for TouchError {}
+
+impl TouchError {
+    /// Error of `operation` failing with `source`.
+    pub fn esp(source: EspErr, operation: &'static str) -> Self {
+        TouchError::Esp {
+            operation,
+            channel: None,
+            args: Vec::new(),
+            source,
+        }
+    }
+
+    /// Record the channel of the operation.
+    pub fn on(mut self, touch_num: TouchPadChannel) -> Self {
+        if let TouchError::Esp { channel, .. } = &mut self {
+            *channel = Some(touch_num);
+        }
+        self
+    }
+
+    /// Record an argument of the operation.
+    pub fn arg(mut self, name: &'static str, value: impl fmt::Debug) -> Self {
+        if let TouchError::Esp { args, .. } = &mut self {
+            args.push((name, format!("{value:?}")));
+        }
+        self
+    }
+
+    /// Channel the error is about, if any.
+    pub fn channel(&self) -> Option<TouchPadChannel> {
+        match self {
+            TouchError::Esp { channel, .. } => *channel,
+            TouchError::NotInitialized
+            | TouchError::FilterNotStarted
+            | TouchError::InvalidConfig
+            | TouchError::Spawn(_) => None,
+            TouchError::ChannelNotEnabled(touch_num)
+            | TouchError::NotCalibrated(touch_num)
+            | TouchError::BadConnection(touch_num)
+            | TouchError::PadTaken(touch_num)
+            | TouchError::InvalidChannel(touch_num)
+            | TouchError::PadMissing(touch_num) => Some(*touch_num),
+        }
+    }
+
+    /// Closest `EspErr`, the code the IDF returns for the same condition.
+    pub fn esp_err(&self) -> EspErr {
+        match self {
+            TouchError::Esp { source, .. } => *source,
+            TouchError::NotInitialized => EspErr::EspFail,
+            TouchError::NotCalibrated(_) => EspErr::EspErrNotFound,
+            TouchError::FilterNotStarted
+            | TouchError::ChannelNotEnabled(_)
+            | TouchError::BadConnection(_)
+            | TouchError::PadTaken(_) => EspErr::EspErrInvalidState,
+            TouchError::InvalidChannel(_)
+            | TouchError::InvalidConfig
+            | TouchError::PadMissing(_) => EspErr::EspErrInvalidArg,
+            TouchError::Spawn(_) => EspErr::EspErrNoMem,
+        }
+    }
+}
+
+impl std::error::Error for TouchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TouchError::Esp { source, .. } => Some(source),
+            TouchError::Spawn(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// E.g. `set_thresh on channel 3 (threshold=500): Invalid argument`.
+impl Display for TouchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TouchError::Esp {
+                operation,
+                channel,
+                args,
+                source,
+            } => {
+                f.write_str(operation)?;
+                if let Some(touch_num) = channel {
+                    write!(f, " on channel {}", *touch_num as u32)?;
+                }
+                for (index, (name, value)) in args.iter().enumerate() {
+                    let separator = if index == 0 { " (" } else { ", " };
+                    write!(f, "{separator}{name}={value}")?;
+                }
+                if !args.is_empty() {
+                    f.write_str(")")?;
+                }
+                write!(f, ": {source}")
+            }
+            TouchError::NotInitialized => f.write_str("Touch driver not initialized"),
+            TouchError::FilterNotStarted => f.write_str("IIR filter not started"),
+            TouchError::ChannelNotEnabled(touch_num) => {
+                write!(f, "Channel {} not enabled", *touch_num as u32)
+            }
+            TouchError::NotCalibrated(touch_num) => {
+                write!(f, "Channel {} not calibrated", *touch_num as u32)
+            }
+            TouchError::BadConnection(touch_num) => {
+                write!(
+                    f,
+                    "Channel {} hardware connection is broken",
+                    *touch_num as u32
+                )
+            }
+            TouchError::PadTaken(touch_num) => {
+                write!(f, "Channel {} already taken", *touch_num as u32)
+            }
+            TouchError::InvalidChannel(touch_num) => {
+                write!(f, "{touch_num:?} is not a touch channel")
+            }
+            TouchError::InvalidConfig => f.write_str("Invalid touch configuration"),
+            TouchError::PadMissing(touch_num) => {
+                write!(
+                    f,
+                    "Channel {} configured without its pad",
+                    *touch_num as u32
+                )
+            }
+            TouchError::Spawn(err) => write!(f, "Cannot spawn the touch thread: {err}"),
+        }
+    }
+}
+
+impl From<TouchError> for EspErr {
+    fn from(err: TouchError) -> Self {
+        err.esp_err()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        assert_eq!(EspErr::try_from(0), Err(0));
+        assert_eq!(EspErr::try_from(0x102), Ok(EspErr::EspErrInvalidArg));
+        let wifi = EspErr::try_from(0x3007).unwrap();
+        assert_eq!(wifi, EspErr::EspErrUnknown(UnknownCode(0x3007)));
+        assert_eq!(i32::from(wifi), 0x3007);
+        assert_eq!(wifi.component(), Some(EspErrComponent::Wifi));
+        assert_eq!(wifi.component_offset(), Some(7));
+        assert_eq!(format!("{:?}", wifi), "EspErrUnknown(0x3007)");
+        assert_eq!(EspErr::return_message(0), Ok(()));
+        assert_eq!(EspErr::return_message(-1), Err(EspErr::EspFail));
+    }
+
+    #[test]
+    fn only_codes_without_a_variant_are_unknown() {
+        for code in [-1, 0x101, 0x107, 0x10D, 0x3000, 0xD000] {
+            let err = EspErr::try_from(code).unwrap();
+            assert!(!matches!(err, EspErr::EspErrUnknown(_)), "{err:?}");
+            assert_eq!(err.code(), code);
+        }
+        match EspErr::try_from(-2) {
+            Ok(EspErr::EspErrUnknown(code)) => assert_eq!(code.code(), -2),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(
+            EspErr::try_from(0x3007).unwrap().to_string(),
+            "WiFi error 0x3007"
+        );
+        assert_eq!(
+            EspErr::try_from(-2).unwrap().to_string(),
+            "Unknown error -0x2"
+        );
+    }
+
+    #[cfg(feature = "esp-idf")]
+    #[test]
+    fn esp_error_conversions() {
+        use esp_idf_svc::sys::EspError;
+
+        let esp_error = |code| EspError::from(code).unwrap();
+        assert_eq!(EspErr::from(esp_error(0x102)), EspErr::EspErrInvalidArg);
+        assert_eq!(
+            EspErr::from(esp_error(0x3007)),
+            EspErr::EspErrUnknown(UnknownCode(0x3007))
+        );
+        for err in [
+            EspErr::EspFail,
+            EspErr::EspErrTimeout,
+            EspErr::EspErrUnknown(UnknownCode(0x3007)),
+        ] {
+            assert_eq!(
+                EspError::try_from(err).map(|err| err.code()),
+                Ok(err.code())
+            );
         }
+        assert_eq!(
+            EspError::try_from(EspErr::EspOk).map(|err| err.code()),
+            Err(EspErr::EspOk)
+        );
     }
 }
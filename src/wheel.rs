@@ -0,0 +1,348 @@
+use crate::baseline::BaselineTracker;
+use crate::slider::{channel_deltas, check_channels};
+use crate::touch_pad_enum::*;
+use crate::touch_pad_error::EspErr;
+
+/// Direction of a wheel rotation. Clockwise goes from each pad of `WheelConfig::channels` to the
+/// next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelDirection {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Events emitted by a `TouchWheel`. Angles are in `0..resolution`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelEvent {
+    TouchDown {
+        angle: u16,
+    },
+    /// The finger turned the wheel by `count` detents.
+    Step {
+        angle: u16,
+        direction: WheelDirection,
+        count: u16,
+    },
+    TouchUp {
+        angle: u16,
+    },
+}
+
+/// Tuning of a `TouchWheel`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WheelConfig {
+    /// Pads of the wheel, in clockwise order. The last pad is next to the first one. At least
+    /// one, each listed once.
+    pub channels: Vec<TouchPadChannel>,
+    /// Number of angle positions in a full turn.
+    pub resolution: u16,
+    /// Delta (baseline - raw) from which a pad counts as touched, must be positive.
+    pub touch_delta: i32,
+    /// Angle positions in one detent.
+    pub detent: u16,
+}
+
+/// Interpolated position of the strongest pad and its neighbours on the ring, in pad units in
+/// `0..deltas.len()`. Deltas below `touch_delta` are ignored, `None` when no pad is touched.
+fn ring_centroid(deltas: &[i32], touch_delta: i32) -> Option<f32> {
+    let (peak, _) = deltas
+        .iter()
+        .enumerate()
+        .filter(|(_, &delta)| delta >= touch_delta)
+        .max_by_key(|(_, &delta)| delta)?;
+    let len = deltas.len() as i32;
+    let offsets: &[i32] = match len {
+        1 => &[0],
+        2 => &[0, 1],
+        _ => &[-1, 0, 1],
+    };
+    let mut sum = 0.0;
+    let mut weighted = 0.0;
+    for &offset in offsets {
+        // The neighbours across the seam are weighted at -1 or +1, not at the far end.
+        let index = (peak as i32 + offset).rem_euclid(len) as usize;
+        let delta = deltas[index].max(0) as f32;
+        sum += delta;
+        weighted += delta * offset as f32;
+    }
+    Some((peak as f32 + weighted / sum).rem_euclid(len as f32))
+}
+
+/// Whether the pads over `touch_delta` form a single run of neighbours on the ring.
+fn is_ring_contiguous(deltas: &[i32], touch_delta: i32) -> bool {
+    let active: Vec<usize> = deltas
+        .iter()
+        .enumerate()
+        .filter(|(_, &delta)| delta >= touch_delta)
+        .map(|(index, _)| index)
+        .collect();
+    let (Some(&first), Some(&last)) = (active.first(), active.last()) else {
+        return true;
+    };
+    let gaps = active
+        .windows(2)
+        .filter(|pair| pair[1] != pair[0] + 1)
+        .count()
+        + usize::from(first + deltas.len() - last != 1);
+    gaps <= 1
+}
+
+/// Circular wheel made of pads in a ring. The absolute angle is interpolated between pads,
+/// including across the seam between the last and the first pad, and the rotation is reported
+/// as detent steps. Every touch starts counting from where the finger lands, so lifting and
+/// re-touching elsewhere does not produce steps.
+#[derive(Debug, Clone)]
+pub struct TouchWheel {
+    config: WheelConfig,
+    angle: Option<u16>,
+    /// Rotation since the last step, in angle positions, positive clockwise.
+    pending: i32,
+}
+
+impl TouchWheel {
+    /// # Error
+    ///
+    /// * ESP_ERR_INVALID_ARG `touch_delta` is not positive, or `channels` is empty, holds `Max`
+    ///   or a pad twice
+    pub fn new(config: WheelConfig) -> Result<Self, EspErr> {
+        if config.touch_delta <= 0 {
+            return Err(EspErr::EspErrInvalidArg);
+        }
+        check_channels(&config.channels)?;
+        Ok(TouchWheel {
+            config,
+            angle: None,
+            pending: 0,
+        })
+    }
+
+    pub fn config(&self) -> &WheelConfig {
+        &self.config
+    }
+
+    /// Current angle, `None` when the wheel is not touched.
+    pub fn angle(&self) -> Option<u16> {
+        self.angle
+    }
+
+    /// Feed the deltas of every channel, indexed by `TouchPadChannel`. Readings where pads that
+    /// are not neighbours are touched at the same time are rejected and leave the wheel
+    /// unchanged.
+    pub fn update(&mut self, deltas: &[i32; TouchPadChannel::ALL.len()]) -> Option<WheelEvent> {
+        let deltas = channel_deltas(&self.config.channels, deltas);
+        if !is_ring_contiguous(&deltas, self.config.touch_delta) {
+            return None;
+        }
+
+        let resolution = self.config.resolution.max(1);
+        let angle = ring_centroid(&deltas, self.config.touch_delta).map(|index| {
+            let angle = (index / deltas.len() as f32 * resolution as f32).round() as u16;
+            angle % resolution
+        });
+        let previous = self.angle;
+        self.angle = angle;
+        match (previous, angle) {
+            (None, Some(angle)) => {
+                self.pending = 0;
+                Some(WheelEvent::TouchDown { angle })
+            }
+            (Some(previous), Some(angle)) => self.rotate(previous, angle),
+            (Some(previous), None) => Some(WheelEvent::TouchUp { angle: previous }),
+            (None, None) => None,
+        }
+    }
+
+    /// Feed the current deltas of a `BaselineTracker`.
+    pub fn update_from(&mut self, tracker: &BaselineTracker) -> Option<WheelEvent> {
+        self.update(&tracker.deltas())
+    }
+
+    fn rotate(&mut self, previous: u16, angle: u16) -> Option<WheelEvent> {
+        let resolution = self.config.resolution.max(1) as i32;
+        // Shortest way around, so crossing the seam is a small move and not a full turn.
+        let mut moved = angle as i32 - previous as i32;
+        if moved > resolution / 2 {
+            moved -= resolution;
+        } else if moved < -resolution / 2 {
+            moved += resolution;
+        }
+        self.pending += moved;
+
+        let detent = self.config.detent.max(1) as i32;
+        let steps = self.pending / detent;
+        if steps == 0 {
+            return None;
+        }
+        self.pending -= steps * detent;
+        let direction = if steps > 0 {
+            WheelDirection::Clockwise
+        } else {
+            WheelDirection::CounterClockwise
+        };
+        Some(WheelEvent::Step {
+            angle,
+            direction,
+            count: steps.unsigned_abs() as u16,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(touch_delta: i32) -> WheelConfig {
+        WheelConfig {
+            channels: vec![
+                TouchPadChannel::Num0,
+                TouchPadChannel::Num1,
+                TouchPadChannel::Num2,
+            ],
+            resolution: 300,
+            touch_delta,
+            detent: 10,
+        }
+    }
+
+    #[test]
+    fn touch_delta_must_be_positive() {
+        assert_eq!(
+            TouchWheel::new(config(0)).err(),
+            Some(EspErr::EspErrInvalidArg)
+        );
+        assert!(TouchWheel::new(config(-5)).is_err());
+
+        let mut wheel = TouchWheel::new(config(1)).unwrap();
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[2] = 40;
+        assert_eq!(
+            wheel.update(&deltas),
+            Some(WheelEvent::TouchDown { angle: 200 })
+        );
+    }
+
+    /// Wheel on `Num0..=Num3`, 100 angle positions per pad and a detent of 25.
+    fn wheel() -> TouchWheel {
+        TouchWheel::new(WheelConfig {
+            channels: TouchPadChannel::ALL[..4].to_vec(),
+            resolution: 400,
+            touch_delta: 10,
+            detent: 25,
+        })
+        .unwrap()
+    }
+
+    /// Deltas of `Num0..=Num3`, the other channels at 0.
+    fn deltas(pads: [i32; 4]) -> [i32; TouchPadChannel::ALL.len()] {
+        let mut deltas = [0; TouchPadChannel::ALL.len()];
+        deltas[..4].copy_from_slice(&pads);
+        deltas
+    }
+
+    fn step(angle: u16, direction: WheelDirection, count: u16) -> Option<WheelEvent> {
+        Some(WheelEvent::Step {
+            angle,
+            direction,
+            count,
+        })
+    }
+
+    #[test]
+    fn angle_is_interpolated_across_the_seam() {
+        let mut wheel = wheel();
+        wheel.update(&deltas([100, 0, 0, 100]));
+        assert_eq!(wheel.angle(), Some(350));
+        wheel.update(&deltas([100, 0, 0, 50]));
+        assert_eq!(wheel.angle(), Some(367));
+        wheel.update(&deltas([100, 50, 0, 0]));
+        assert_eq!(wheel.angle(), Some(33));
+    }
+
+    #[test]
+    fn crossing_the_seam_clockwise() {
+        let mut wheel = wheel();
+        assert_eq!(
+            wheel.update(&deltas([0, 0, 0, 100])),
+            Some(WheelEvent::TouchDown { angle: 300 })
+        );
+        assert_eq!(
+            wheel.update(&deltas([100, 0, 0, 100])),
+            step(350, WheelDirection::Clockwise, 2)
+        );
+        assert_eq!(
+            wheel.update(&deltas([100, 0, 0, 0])),
+            step(0, WheelDirection::Clockwise, 2)
+        );
+    }
+
+    #[test]
+    fn crossing_the_seam_counter_clockwise() {
+        let mut wheel = wheel();
+        wheel.update(&deltas([100, 0, 0, 0]));
+        assert_eq!(
+            wheel.update(&deltas([100, 0, 0, 100])),
+            step(350, WheelDirection::CounterClockwise, 2)
+        );
+        assert_eq!(
+            wheel.update(&deltas([0, 0, 0, 100])),
+            step(300, WheelDirection::CounterClockwise, 2)
+        );
+    }
+
+    #[test]
+    fn moves_add_up_to_detents() {
+        let mut wheel = wheel();
+        wheel.update(&deltas([100, 0, 0, 0]));
+        // 10 positions, less than a detent.
+        assert_eq!(wheel.update(&deltas([90, 10, 0, 0])), None);
+        assert_eq!(wheel.angle(), Some(10));
+        // 20 more, one detent and 5 left over.
+        assert_eq!(
+            wheel.update(&deltas([70, 30, 0, 0])),
+            step(30, WheelDirection::Clockwise, 1)
+        );
+        // 20 more complete the second detent.
+        assert_eq!(
+            wheel.update(&deltas([50, 50, 0, 0])),
+            step(50, WheelDirection::Clockwise, 1)
+        );
+    }
+
+    #[test]
+    fn non_adjacent_pads_are_rejected() {
+        let mut wheel = wheel();
+        wheel.update(&deltas([100, 0, 0, 0]));
+        assert_eq!(wheel.update(&deltas([100, 0, 100, 0])), None);
+        assert_eq!(wheel.angle(), Some(0));
+        assert!(!is_ring_contiguous(&[100, 0, 100, 0], 10));
+        assert!(is_ring_contiguous(&[100, 100, 0, 100], 10));
+    }
+
+    #[test]
+    fn retouch_does_not_step() {
+        let mut wheel = wheel();
+        wheel.update(&deltas([90, 10, 0, 0]));
+        assert_eq!(
+            wheel.update(&deltas([0; 4])),
+            Some(WheelEvent::TouchUp { angle: 10 })
+        );
+        assert_eq!(
+            wheel.update(&deltas([0, 0, 100, 0])),
+            Some(WheelEvent::TouchDown { angle: 200 })
+        );
+        // The 10 positions of the previous touch are not carried over.
+        assert_eq!(wheel.update(&deltas([0, 0, 85, 15])), None);
+    }
+
+    #[test]
+    fn channels_are_checked() {
+        let config = |channels: Vec<TouchPadChannel>| WheelConfig {
+            channels,
+            ..config(10)
+        };
+        use TouchPadChannel::*;
+        assert!(TouchWheel::new(config(vec![])).is_err());
+        assert!(TouchWheel::new(config(vec![Num0, Num1, Num0])).is_err());
+        assert!(TouchWheel::new(config(vec![Num0, Max])).is_err());
+    }
+}